@@ -1,5 +1,7 @@
 // use tauri::Wry;
 use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+use crate::jules::{JulesAuth, JulesConfig};
 // use serde_json::json;
 
 // Placeholders for secrets
@@ -34,9 +36,55 @@ pub fn get_google_token<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<
     Some("mock_google_token".to_string())
 }
 
+const JULES_API_KEY: &str = "jules_api_key";
+const JULES_BASE_URL: &str = "jules_base_url";
+
+/// An API key, when set, takes precedence over the Google token.
+/// The base URL comes from the app store, then JULES_API_BASE_URL.
+pub fn get_jules_config<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<JulesConfig> {
+    let store = app.store("auth_store.json").ok()?;
+
+    let api_key = store.get(JULES_API_KEY)
+        .and_then(|key| key.as_str().map(|s| s.to_string()))
+        .filter(|key| !key.is_empty());
+    let auth = match api_key {
+        Some(key) => JulesAuth::ApiKey(key),
+        None => JulesAuth::Bearer(get_google_token(app)?),
+    };
+
+    let base_url = app.store(APP_STORE).ok()
+        .and_then(|store| store.get(JULES_BASE_URL))
+        .and_then(|url| url.as_str().map(|s| s.to_string()))
+        .or_else(|| std::env::var("JULES_API_BASE_URL").ok())
+        .filter(|url| !url.is_empty());
+
+    Some(JulesConfig { base_url, auth })
+}
+
+/// Empty or missing values clear the setting.
+pub fn set_jules_config<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    base_url: Option<String>,
+    api_key: Option<String>
+) -> Result<(), String> {
+    let auth_store = app.store("auth_store.json").map_err(|e| e.to_string())?;
+    match api_key.filter(|key| !key.is_empty()) {
+        Some(key) => auth_store.set(JULES_API_KEY, serde_json::json!(key)),
+        None => { auth_store.delete(JULES_API_KEY); }
+    }
+    auth_store.save().map_err(|e| e.to_string())?;
+
+    let app_store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+    match base_url.filter(|url| !url.is_empty()) {
+        Some(url) => app_store.set(JULES_BASE_URL, serde_json::json!(url)),
+        None => { app_store.delete(JULES_BASE_URL); }
+    }
+    app_store.save().map_err(|e| e.to_string())
+}
+
 pub fn check_auth_status<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> crate::types::AuthState {
     let github = get_github_token(app).is_some();
-    let google = get_google_token(app).is_some();
+    let google = get_jules_config(app).is_some();
 
     crate::types::AuthState {
         github_authenticated: github,
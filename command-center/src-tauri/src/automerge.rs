@@ -0,0 +1,314 @@
+use crate::auth;
+use crate::github::{ChecksState, GithubClient};
+use crate::pipeline::is_retryable;
+use crate::poller::emit_update;
+use crate::types::{AgentMode, JobParams, JobState, JobStatus, JobUpdateEvent, MergeMethod, MergePolicy};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Opt-in merging for Auto mode jobs. Once a job's PR is ready, its policy (the
+// job's own, else its repo's) decides: the diff size and paths are checked
+// first, then required checks are waited for, then the PR is merged. Every
+// decision goes to the job log.
+
+const REPO_POLICIES_KEY: &str = "merge_policies";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// How long a commit may go without any checks before the repo is taken to have none
+const NO_CHECKS_GRACE: Duration = Duration::from_secs(5 * 60);
+
+// Jobs with a merge in progress, so setting a policy twice doesn't merge twice
+static ACTIVE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct ActiveGuard(String);
+
+impl ActiveGuard {
+    fn claim(job_id: &str) -> Option<Self> {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        if active.iter().any(|id| id == job_id) {
+            return None;
+        }
+        active.push(job_id.to_string());
+        Some(ActiveGuard(job_id.to_string()))
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).retain(|id| *id != self.0);
+    }
+}
+
+/// Keyed by owner/repo.
+pub fn repo_policies<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> HashMap<String, MergePolicy> {
+    app.store(APP_STORE).ok()
+        .and_then(|store| store.get(REPO_POLICIES_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// None removes the repo's policy.
+pub fn set_repo_policy<R: tauri::Runtime>(app: &tauri::AppHandle<R>, repo: &str, policy: Option<MergePolicy>) -> Result<(), String> {
+    let mut policies = repo_policies(app);
+    match policy {
+        Some(policy) => policies.insert(repo.to_string(), policy),
+        None => policies.remove(repo),
+    };
+    let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+    store.set(REPO_POLICIES_KEY, serde_json::json!(policies));
+    store.save().map_err(|e| e.to_string())
+}
+
+fn policy_for(app: &tauri::AppHandle, job: &JobState) -> Option<MergePolicy> {
+    job.merge_policy.clone().or_else(|| repo_policies(app).remove(&job.github_repo))
+}
+
+fn is_auto(job: &JobState) -> bool {
+    matches!(
+        job.params,
+        Some(JobParams::Scaffold { mode: AgentMode::Auto, .. } | JobParams::Uplink { mode: AgentMode::Auto, .. })
+    )
+}
+
+// "dir/" matches everything under dir, a pattern with "*" is a wildcard over
+// the whole path (so "*.md" matches docs/a.md), anything else is one file.
+fn path_allowed(path: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| {
+        if pattern.ends_with('/') {
+            path.starts_with(pattern.as_str())
+        } else if pattern.contains('*') {
+            wildcard(pattern, path)
+        } else {
+            path == pattern
+        }
+    })
+}
+
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+// Logs without touching the status, which the user may have changed meanwhile
+fn log(app: &tauri::AppHandle, job_id: &str, line: String) {
+    let Some(job) = app.state::<crate::AppState>().jobs.get(job_id) else { return };
+    emit_update(app, JobUpdateEvent::new(job_id, job.status, vec![line]));
+}
+
+// Logs an outcome that stands until the policy changes, so a restart doesn't
+// evaluate the PR again
+fn decided(app: &tauri::AppHandle, job_id: &str, line: String) {
+    app.state::<crate::AppState>().update_job(job_id, |job| job.merge_decided = true);
+    log(app, job_id, line);
+}
+
+/// Runs run() on its own blocking thread, so waiting for checks doesn't hold
+/// up whoever noticed the PR was ready.
+pub fn spawn(app: &tauri::AppHandle, gh: GithubClient, job_id: &str) {
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || run(&app, &gh, &job_id));
+}
+
+// Spawns run() for every PR-ready job the filter picks
+fn spawn_where(app: &tauri::AppHandle, pick: impl Fn(&JobState) -> bool) {
+    let state = app.state::<crate::AppState>();
+    let jobs: Vec<JobState> = state.jobs.all().into_iter()
+        .filter(|job| job.status == JobStatus::PrReady && pick(job))
+        .collect();
+    if jobs.is_empty() {
+        return;
+    }
+    let Some(token) = auth::get_github_token(app) else { return };
+    let gh = GithubClient::new(token).with_cache(state.github_cache.clone());
+    for job in jobs {
+        spawn(app, gh.clone(), &job.id);
+    }
+}
+
+/// Picks up merges a restart cut short, e.g. while waiting for checks.
+pub fn resume_all(app: &tauri::AppHandle) {
+    spawn_where(app, |job| !job.merge_decided && policy_for(app, job).is_some());
+}
+
+/// Evaluates the repo's ready PRs against its new policy. Jobs with their
+/// own policy are unaffected.
+pub fn repo_policy_changed(app: &tauri::AppHandle, repo: &str) {
+    spawn_where(app, |job| job.github_repo == repo && job.merge_policy.is_none());
+}
+
+/// Merges the job's PR if its policy allows. Does nothing for jobs without a
+/// policy or without a ready PR. Blocks while waiting for checks.
+pub fn run(app: &tauri::AppHandle, gh: &GithubClient, job_id: &str) {
+    let Some(job) = app.state::<crate::AppState>().jobs.get(job_id) else { return };
+    let Some(policy) = policy_for(app, &job) else { return };
+    let (JobStatus::PrReady, Some(pr)) = (&job.status, &job.pr_details) else { return };
+    let Some(_active) = ActiveGuard::claim(job_id) else { return };
+    app.state::<crate::AppState>().update_job(job_id, |job| job.merge_decided = false);
+
+    if !is_auto(&job) {
+        decided(app, job_id, "Auto-merge: skipped, policies only apply to Auto mode jobs.".to_string());
+        return;
+    }
+    // Older jobs stored the repo URL rather than "owner/repo"
+    let Ok((owner, repo)) = crate::uplink_engine::parse_repo_url(&job.github_repo) else { return };
+
+    let head_sha = match check(app, gh, job_id, &owner, &repo, pr.number, &policy) {
+        Ok(sha) => sha,
+        Err(reason) => {
+            decided(app, job_id, format!("Auto-merge: not merging PR #{}: {}.", pr.number, reason));
+            return;
+        }
+    };
+    // Merges exactly the commit that was checked, not one pushed since
+    match gh.merge_pull_request(&owner, &repo, pr.number, policy.method, Some(head_sha.as_str())) {
+        Ok(_) => {
+            let method = match policy.method {
+                MergeMethod::Merge => "merge commit",
+                MergeMethod::Squash => "squash",
+                MergeMethod::Rebase => "rebase",
+            };
+            emit_update(app, JobUpdateEvent::new(job_id, JobStatus::Merged, vec![format!("Auto-merge: merged PR #{} ({}).", pr.number, method)]));
+        }
+        Err(e) => decided(app, job_id, format!("Auto-merge: merging PR #{} failed: {}", pr.number, e)),
+    }
+}
+
+// The head commit that may be merged, else why not
+fn check(app: &tauri::AppHandle, gh: &GithubClient, job_id: &str, owner: &str, repo: &str, number: u64, policy: &MergePolicy) -> Result<String, String> {
+    let pr = gh.get_pull_request(owner, repo, number)?;
+    if pr.merged {
+        return Err("it is already merged".to_string());
+    }
+    if !pr.open {
+        return Err("it is closed".to_string());
+    }
+
+    if let Some(max) = policy.max_changed_lines {
+        if pr.changed_lines > max {
+            return Err(format!("{} changed lines, over the cap of {}", pr.changed_lines, max));
+        }
+        log(app, job_id, format!("Auto-merge: {} changed lines, within the cap of {}.", pr.changed_lines, max));
+    }
+
+    if !policy.allowed_paths.is_empty() {
+        let files = gh.list_pull_request_files(owner, repo, number)?;
+        if let Some(file) = files.iter().find(|f| !path_allowed(f, &policy.allowed_paths)) {
+            return Err(format!("{} is outside the allowed paths", file));
+        }
+        log(app, job_id, format!("Auto-merge: all {} changed files are within the allowed paths.", files.len()));
+    }
+
+    if !policy.require_checks {
+        log(app, job_id, "Auto-merge: checks not required by the policy.".to_string());
+        return Ok(pr.head_sha);
+    }
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(policy.check_timeout_mins * 60);
+    let mut waiting_on: Option<Vec<String>> = None;
+    loop {
+        match gh.get_checks(owner, repo, &pr.head_sha) {
+            Ok(ChecksState::Passed) => {
+                log(app, job_id, "Auto-merge: checks passed.".to_string());
+                return Ok(pr.head_sha);
+            }
+            Ok(ChecksState::NoChecks) if started.elapsed() >= NO_CHECKS_GRACE => {
+                log(app, job_id, format!(
+                    "Auto-merge: no checks reported after {} minutes, so the repo has none.",
+                    NO_CHECKS_GRACE.as_secs() / 60
+                ));
+                return Ok(pr.head_sha);
+            }
+            Ok(ChecksState::NoChecks) => {
+                if waiting_on.is_none() {
+                    log(app, job_id, "Auto-merge: waiting for checks to start.".to_string());
+                    waiting_on = Some(vec![]);
+                }
+            }
+            Ok(ChecksState::Failed(names)) => return Err(format!("checks failed: {}", names.join(", "))),
+            Ok(ChecksState::Pending(names)) => {
+                if waiting_on.as_ref() != Some(&names) {
+                    log(app, job_id, format!("Auto-merge: waiting for checks: {}", names.join(", ")));
+                    waiting_on = Some(names);
+                }
+            }
+            // Try again next round
+            Err(e) if is_retryable(&e) => {}
+            Err(e) => return Err(e),
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("checks still pending after {} minutes", policy.check_timeout_mins));
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+
+        // The user may have merged by hand or dropped the policy meanwhile
+        let job = app.state::<crate::AppState>().jobs.get(job_id).ok_or("the job no longer exists")?;
+        if job.status != JobStatus::PrReady {
+            return Err("the job is no longer waiting to merge".to_string());
+        }
+        if policy_for(app, &job).is_none() {
+            return Err("the policy was removed".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_matches_across_the_whole_text() {
+        let cases = [
+            ("*", "", true),
+            ("*", "src/main.rs", true),
+            ("*.md", "README.md", true),
+            ("*.md", "docs/guide.md", true),
+            ("*.md", "README.mdx", false),
+            ("docs/*", "docs/a/b.md", true),
+            ("docs/*", "src/docs/a.md", false),
+            ("src/*/mod.rs", "src/jobs/mod.rs", true),
+            ("src/*/mod.rs", "src/mod.rs", false),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "aXcYb", false),
+            ("a*a", "a", false),
+            ("a*a", "aa", true),
+            ("*test*", "src/tests/it.rs", true),
+            ("**", "anything", true),
+        ];
+        for (pattern, text, expected) in cases {
+            assert_eq!(wildcard(pattern, text), expected, "{} ~ {}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn paths_match_dirs_wildcards_or_exact_files() {
+        let patterns: Vec<String> = ["docs/", "*.md", "Cargo.lock"].iter().map(|p| p.to_string()).collect();
+        let cases = [
+            ("docs/guide.txt", true),
+            ("docs/nested/a.png", true),
+            ("docsx/a.txt", false),
+            ("src/notes.md", true),
+            ("Cargo.lock", true),
+            ("sub/Cargo.lock", false),
+            ("Cargo.toml", false),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(path_allowed(path, &patterns), expected, "{}", path);
+        }
+        assert!(!path_allowed("README.md", &[]));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::auth;
+use crate::github::GithubClient;
+use crate::jules::{JulesClient, SourceResolution};
+use crate::pipeline::{self, JobContext};
+use crate::prompts;
+use crate::types::{AgentMode, JobParams, JobState, JobStatus, SessionSettings};
+use crate::uplink_engine;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// A batch applies one context and prompt to many repos. Each repo becomes an
+// ordinary uplink job tagged with the batch id, so children queue for Jules
+// like any other job and a large batch drains at the pace the limits allow.
+// Before that, a few children at a time get to change their repo on GitHub.
+
+const BATCHES_KEY: &str = "batches";
+const MAX_REPOS: usize = 100;
+const GITHUB_WORKERS: usize = 4; // Children changing their repo on GitHub at once
+
+// Serializes the load-modify-save in save()
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+static GITHUB_PERMITS: Permits = Permits { in_use: Mutex::new(0), freed: Condvar::new() };
+
+struct Permits {
+    in_use: Mutex<usize>,
+    freed: Condvar,
+}
+
+/// Lets a batch child change its repo on GitHub. Children across all batches
+/// share GITHUB_WORKERS permits, so a large batch doesn't send every commit at
+/// once. StartSession drops it, since the scheduler paces the rest.
+pub struct GithubPermit;
+
+impl Drop for GithubPermit {
+    fn drop(&mut self) {
+        *GITHUB_PERMITS.in_use.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        GITHUB_PERMITS.freed.notify_one();
+    }
+}
+
+// Waits for a permit. None if the job is cancelled first; its pipeline then
+// stops before the first step.
+fn github_permit(cancel: &AtomicBool) -> Option<GithubPermit> {
+    let mut in_use = GITHUB_PERMITS.in_use.lock().unwrap();
+    while *in_use >= GITHUB_WORKERS {
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        in_use = GITHUB_PERMITS.freed.wait_timeout(in_use, Duration::from_millis(250)).unwrap().0;
+    }
+    *in_use += 1;
+    Some(GithubPermit)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum BatchTarget {
+    Repos { repos: Vec<String> }, // URLs or owner/repo
+    Org {
+        org: String,
+        #[serde(default)]
+        filter: Option<String>, // Case-insensitive substring of the repo name
+        #[serde(default)]
+        include_forks: bool,
+        #[serde(default)]
+        include_archived: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedRepo {
+    pub repo: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Batch {
+    pub id: String,
+    pub target: BatchTarget,
+    pub context: String, // "{repo}" is replaced with each child's owner/repo
+    pub prompt: String,
+    pub mode: AgentMode,
+    pub job_ids: Vec<String>,
+    pub skipped: Vec<SkippedRepo>,
+    pub created_at: u64, // Unix seconds
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub batch_id: String,
+    pub total: usize,
+    pub pr_ready: usize, // Including merged
+    pub failed: usize,
+    pub cancelled: usize,
+    pub completed: usize, // Finished without a PR
+    pub queued: usize,
+    pub interrupted: usize,
+    pub running: usize,
+    pub done: bool, // Every child is merged, has a PR or has otherwise ended
+}
+
+fn load_all<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<Batch> {
+    app.store(APP_STORE).ok()
+        .and_then(|store| store.get(BATCHES_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn save<R: tauri::Runtime>(app: &tauri::AppHandle<R>, batch: &Batch) -> Result<(), String> {
+    let _lock = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+    let mut batches = load_all(app);
+    batches.retain(|b| b.id != batch.id);
+    batches.push(batch.clone());
+    store.set(BATCHES_KEY, serde_json::json!(batches));
+    store.save().map_err(|e| e.to_string())
+}
+
+pub fn get<R: tauri::Runtime>(app: &tauri::AppHandle<R>, batch_id: &str) -> Option<Batch> {
+    load_all(app).into_iter().find(|b| b.id == batch_id)
+}
+
+pub fn list<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<Batch> {
+    load_all(app)
+}
+
+/// Counts the children by status. Children are read from the job store, so
+/// this is always current.
+pub fn progress(app: &tauri::AppHandle, batch: &Batch) -> BatchProgress {
+    let jobs = &app.state::<crate::AppState>().jobs;
+    let mut progress = BatchProgress { batch_id: batch.id.clone(), total: batch.job_ids.len(), ..BatchProgress::default() };
+    let mut finished = 0;
+    for job in batch.job_ids.iter().filter_map(|id| jobs.get(id)) {
+        match job.status {
+            JobStatus::PrReady | JobStatus::Merged => progress.pr_ready += 1,
+            JobStatus::Failed { .. } => progress.failed += 1,
+            JobStatus::Cancelled => progress.cancelled += 1,
+            JobStatus::Completed => progress.completed += 1,
+            JobStatus::Queued => progress.queued += 1,
+            JobStatus::Interrupted => progress.interrupted += 1,
+            _ => progress.running += 1,
+        }
+        if job.status.ends_session() {
+            finished += 1;
+        }
+    }
+    progress.done = finished == progress.total;
+    progress
+}
+
+/// Sends BATCH_UPDATE for the batch a job belongs to. Called when a child changes status.
+pub fn emit_progress(app: &tauri::AppHandle, batch_id: &str) {
+    let Some(batch) = get(app, batch_id) else { return };
+    if let Err(e) = app.emit("BATCH_UPDATE", progress(app, &batch)) {
+        println!("Failed to emit event: {}", e);
+    }
+}
+
+// The repos to create children for, and the ones left out with a reason
+fn resolve_repos(gh: &GithubClient, target: &BatchTarget) -> Result<(Vec<String>, Vec<SkippedRepo>), String> {
+    let mut repos: Vec<String> = vec![];
+    let mut skipped = vec![];
+    match target {
+        BatchTarget::Repos { repos: urls } => {
+            for url in urls.iter().filter(|u| !u.trim().is_empty()) {
+                let (owner, repo) = uplink_engine::parse_repo_url(url.trim())?;
+                let full_name = format!("{}/{}", owner, repo);
+                if !repos.contains(&full_name) {
+                    repos.push(full_name);
+                }
+            }
+        }
+        BatchTarget::Org { org, filter, include_forks, include_archived } => {
+            let filter = filter.as_ref().map(|f| f.to_lowercase());
+            for repo in gh.list_org_repos(org)? {
+                let name = repo.full_name.split_once('/').map_or(repo.full_name.as_str(), |(_, n)| n);
+                if (repo.fork && !include_forks)
+                    || (repo.archived && !include_archived)
+                    || filter.as_ref().is_some_and(|f| !name.to_lowercase().contains(f))
+                {
+                    continue;
+                }
+                if repo.can_push {
+                    repos.push(repo.full_name);
+                } else {
+                    skipped.push(SkippedRepo { repo: repo.full_name, reason: "No write access".to_string() });
+                }
+            }
+        }
+    }
+    Ok((repos, skipped))
+}
+
+/// Creates the batch and starts one uplink job per repo. Repos Jules can't see
+/// are skipped up front, like start_uplink_job does for a single repo.
+/// Must be called on a blocking thread.
+pub fn start(app: &tauri::AppHandle, target: BatchTarget, context: String, prompt: String, mode: AgentMode) -> Result<Batch, String> {
+    prompts::validate(&prompt)?;
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
+    let state = app.state::<crate::AppState>();
+    let gh = GithubClient::new(gh_token.clone()).with_cache(state.github_cache.clone());
+
+    let (repos, mut skipped) = resolve_repos(&gh, &target)?;
+    if repos.len() > MAX_REPOS {
+        return Err(format!("{} repositories matched, a batch takes at most {}", repos.len(), MAX_REPOS));
+    }
+
+    let jules = JulesClient::new(jules_config.clone());
+    let mut ready = vec![];
+    for repo in repos {
+        let (owner, name) = repo.split_once('/').ok_or("Unexpected repo name")?;
+        match jules.resolve_source(owner, name) {
+            Ok(SourceResolution::Connected { .. }) => ready.push(repo),
+            Ok(SourceResolution::NotInstalled { .. }) => {
+                skipped.push(SkippedRepo { repo, reason: "Jules is not installed on this repo".to_string() });
+            }
+            Err(e) => skipped.push(SkippedRepo { repo, reason: e }),
+        }
+    }
+    if ready.is_empty() {
+        return Err(match skipped.len() {
+            0 => "No repositories matched".to_string(),
+            n => format!("None of the {} matching repositories can be used", n),
+        });
+    }
+
+    let prompt = match prompt.trim() {
+        "" => uplink_engine::DEFAULT_PROMPT.to_string(),
+        p => p.to_string(),
+    };
+    let mut batch = Batch {
+        id: uuid::Uuid::new_v4().to_string(),
+        target,
+        context,
+        prompt,
+        mode,
+        job_ids: vec![],
+        skipped,
+        created_at: chrono::Utc::now().timestamp() as u64,
+    };
+
+    let mut children = vec![];
+    for repo in ready {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let context = prompts::expand(&batch.context, &[("repo", &repo)]);
+        let mut job = JobState::new(job_id.clone(), repo.clone(), JobStatus::UploadingContext, JobParams::Uplink {
+            repo_url: repo.clone(),
+            context: context.clone(),
+            mode: batch.mode.clone(),
+            prompt: Some(batch.prompt.clone()),
+            session: SessionSettings::default(),
+        });
+        job.batch_id = Some(batch.id.clone());
+        state.jobs.insert(job);
+        batch.job_ids.push(job_id.clone());
+        children.push((job_id, repo, context));
+    }
+    save(app, &batch)?;
+
+    for (job_id, repo, context) in children {
+        let handle = app.clone();
+        let id = job_id.clone();
+        let (gh_token, jules_config, mode, prompt) = (gh_token.clone(), jules_config.clone(), batch.mode.clone(), batch.prompt.clone());
+        pipeline::spawn(app.clone(), job_id, move || {
+            let mut ctx = JobContext::new(handle, id, gh_token, jules_config, mode);
+            ctx.github_permit = github_permit(&ctx.cancel);
+            uplink_engine::run_uplink_job(ctx, &repo, &context, &prompt, &SessionSettings::default())
+        });
+    }
+
+    emit_progress(app, &batch.id);
+    Ok(batch)
+}
@@ -0,0 +1,108 @@
+use crate::pipeline::{JobContext, Pipeline};
+use serde::Serialize;
+
+// A dry run walks a job's steps with Step::plan instead of Step::run. Every
+// lookup a real run would make still happens, but nothing is created, nothing
+// runs over SSH and no Jules session starts. The report says what each step
+// would have done.
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAction {
+    pub step: String,        // Step::name
+    pub description: String, // Step::describe
+    pub details: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunReport {
+    pub repo: String, // owner/repo the job would work on
+    pub actions: Vec<PlannedAction>,
+    pub error: Option<String>, // Why a real run would stop at the last action
+}
+
+/// What the start commands return: the new job's id, or the report for a dry run.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum JobStart {
+    Started(String),
+    DryRun(DryRunReport),
+}
+
+/// Stops at the first step whose plan fails, since a real run would stop there too.
+pub fn plan(mut ctx: JobContext, steps: Pipeline) -> DryRunReport {
+    let mut actions = vec![];
+    let mut error = None;
+    for step in &steps {
+        let result = step.plan(&mut ctx);
+        actions.push(PlannedAction {
+            step: step.name().to_string(),
+            description: step.describe(),
+            details: result.clone().unwrap_or_default(),
+        });
+        if let Err(e) = result {
+            error = Some(format!("{}: {}", step.name(), e));
+            break;
+        }
+    }
+    DryRunReport { repo: format!("{}/{}", ctx.owner, ctx.repo), actions, error }
+}
+
+/// Line diff of `old` against `new`: unchanged lines start with two spaces,
+/// removed ones with "- " and added ones with "+ ". A final newline, or the
+/// lack of one, is not a change.
+pub fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_diff_cases() {
+        let cases: [(&str, &str, &str, &[&str]); 7] = [
+            ("both empty", "", "", &[]),
+            ("empty old", "", "a\nb\n", &["+ a", "+ b"]),
+            ("empty new", "a\nb\n", "", &["- a", "- b"]),
+            ("identical", "a\nb\n", "a\nb\n", &["  a", "  b"]),
+            ("insertion in the middle", "a\nc\n", "a\nb\nc\n", &["  a", "+ b", "  c"]),
+            ("changed line", "a\nb\nc\n", "a\nB\nc\n", &["  a", "- b", "+ B", "  c"]),
+            ("trailing newline only", "a\nb", "a\nb\n", &["  a", "  b"]),
+        ];
+        for (name, old, new, expected) in cases {
+            assert_eq!(line_diff(old, new), expected, "{}", name);
+        }
+    }
+}
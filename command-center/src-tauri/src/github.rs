@@ -1,13 +1,57 @@
 use std::time::Duration;
 use std::thread;
 // use crate::auth::get_github_token;
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
 use serde_json::json;
 use base64::Engine;
+use std::sync::{Arc, Mutex};
+use crate::http_cache::{CacheEntry, ResponseCache};
+use crate::types::MergeMethod;
+
+pub struct CodespaceInfo {
+    pub name: String,
+    pub display_name: String,
+    pub repository: String, // owner/repo
+}
+
+pub struct DeployKeyInfo {
+    pub id: u64,
+    pub title: String,
+}
+
+pub struct RepoInfo {
+    pub full_name: String,
+    pub fork: bool,
+    pub archived: bool,
+    pub can_push: bool,
+}
+
+pub struct PullRequestInfo {
+    pub head_sha: String,
+    pub open: bool,
+    pub merged: bool,
+    pub changed_lines: u64, // Additions + deletions
+}
 
+pub enum ChecksState {
+    Passed,
+    NoChecks, // Nothing reported yet; CI may not have picked the commit up
+    Pending(Vec<String>), // Names of the checks still running
+    Failed(Vec<String>),
+}
+
+// Enough for any account's codespaces or a repo's deploy keys
+const MAX_LIST_PAGES: u32 = 20;
+// A commit with more check runs than this isn't merged automatically
+const MAX_CHECK_PAGES: u32 = 10;
+
+#[derive(Clone)]
 pub struct GithubClient {
     token: String,
     client: Client,
+    cache: Option<Arc<Mutex<ResponseCache>>>,
 }
 
 impl GithubClient {
@@ -18,7 +62,80 @@ impl GithubClient {
                 .user_agent("command-center-agent")
                 .build()
                 .unwrap_or_default(),
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(mut self, cache: Arc<Mutex<ResponseCache>>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    // GET with If-None-Match / If-Modified-Since. A 304 is answered from the cache
+    // and reported as 200 so callers don't need to care where the body came from.
+    fn get_json(&self, url: &str) -> Result<(StatusCode, serde_json::Value), String> {
+        let cached = self.cache.as_ref().and_then(|c| c.lock().unwrap().get(url));
+
+        let mut req = self.client.get(url).bearer_auth(&self.token);
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &entry.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, modified);
+            }
+        }
+        let res = req.send().map_err(|e| e.to_string())?;
+        let status = res.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = cached {
+                return Ok((StatusCode::OK, entry.body));
+            }
+        }
+
+        if !status.is_success() {
+            if let Some(cache) = &self.cache {
+                cache.lock().unwrap().remove(url);
+            }
+            return Ok((status, serde_json::Value::Null));
+        }
+
+        let etag = header_string(&res, ETAG);
+        let last_modified = header_string(&res, LAST_MODIFIED);
+
+        let body: serde_json::Value = res.json().map_err(|e| e.to_string())?;
+        if let Some(cache) = &self.cache {
+            if etag.is_some() || last_modified.is_some() {
+                cache.lock().unwrap().insert(url, CacheEntry::new(etag, last_modified, body.clone()));
+            }
         }
+        Ok((status, body))
+    }
+
+    // GETs `url` 100 entries at a time until a short page or `max_pages`.
+    // `items` picks the entries out of a page body.
+    fn get_pages(
+        &self,
+        url: &str,
+        max_pages: u32,
+        error: &str,
+        items: impl Fn(&serde_json::Value) -> Option<&Vec<serde_json::Value>>
+    ) -> Result<Vec<serde_json::Value>, String> {
+        let mut all = vec![];
+        for page in 1..=max_pages {
+            let (status, body) = self.get_json(&format!("{}?per_page=100&page={}", url, page))?;
+            if !status.is_success() {
+                return Err(format!("{}: {}", error, status));
+            }
+            let page_items = items(&body).cloned().unwrap_or_default();
+            let last_page = page_items.len() < 100;
+            all.extend(page_items);
+            if last_page {
+                break;
+            }
+        }
+        Ok(all)
     }
 
     pub fn create_private_repo(&self, name: &str) -> Result<String, String> {
@@ -47,7 +164,7 @@ impl GithubClient {
         Ok(body["full_name"].as_str().unwrap_or("unknown/repo").to_string())
     }
 
-    pub fn create_codespace(&self, repo_owner: &str, repo_name: &str) -> Result<String, String> {
+    pub fn create_codespace(&self, repo_owner: &str, repo_name: &str, display_name: &str) -> Result<String, String> {
          if self.token.starts_with("mock") {
             println!("MOCK: Creating codespace for {}/{}", repo_owner, repo_name);
             return Ok("mock_codespace_id_123".to_string());
@@ -57,7 +174,8 @@ impl GithubClient {
         let res = self.client.post(&url)
             .bearer_auth(&self.token)
             .json(&json!({
-                "machine": "basicLinux"
+                "machine": "basicLinux",
+                "display_name": display_name
             }))
             .send()
             .map_err(|e| e.to_string())?;
@@ -80,13 +198,9 @@ impl GithubClient {
         // Loop and check status
         let url = format!("https://api.github.com/user/codespaces/{}", codespace_name);
         for _ in 0..60 { // Try for 5 minutes
-            let res = self.client.get(&url)
-                .bearer_auth(&self.token)
-                .send()
-                .map_err(|e| e.to_string())?;
+            let (status, body) = self.get_json(&url)?;
 
-            if res.status().is_success() {
-                let body: serde_json::Value = res.json().map_err(|e| e.to_string())?;
+            if status.is_success() {
                 let state = body["state"].as_str().unwrap_or("");
                 if state == "Available" {
                     return Ok(());
@@ -97,6 +211,24 @@ impl GithubClient {
         Err("Codespace failed to become available".to_string())
     }
 
+    pub fn list_codespaces(&self) -> Result<Vec<CodespaceInfo>, String> {
+        if self.token.starts_with("mock") {
+            return Ok(vec![]);
+        }
+
+        let codespaces = self.get_pages(
+            "https://api.github.com/user/codespaces",
+            MAX_LIST_PAGES,
+            "GitHub API Error (List Codespaces)",
+            |body| body["codespaces"].as_array(),
+        )?;
+        Ok(codespaces.iter().map(|c| CodespaceInfo {
+            name: c["name"].as_str().unwrap_or("").to_string(),
+            display_name: c["display_name"].as_str().unwrap_or("").to_string(),
+            repository: c["repository"]["full_name"].as_str().unwrap_or("").to_string(),
+        }).collect())
+    }
+
     pub fn delete_codespace(&self, codespace_name: &str) -> Result<(), String> {
          if self.token.starts_with("mock") {
             println!("MOCK: Deleting codespace {}", codespace_name);
@@ -140,6 +272,40 @@ impl GithubClient {
         Ok(body["id"].as_u64().unwrap_or(0))
     }
 
+    pub fn list_org_repos(&self, org: &str) -> Result<Vec<RepoInfo>, String> {
+        if self.token.starts_with("mock") {
+            return Ok(["api", "web", "cli"].iter().map(|name| RepoInfo {
+                full_name: format!("{}/{}", org, name),
+                fork: false,
+                archived: false,
+                can_push: true,
+            }).collect());
+        }
+
+        // 10 pages of 100 is plenty for a batch; anything past that is ignored
+        let url = format!("https://api.github.com/orgs/{}/repos", org);
+        let repos = self.get_pages(&url, 10, &format!("Failed to list repos for {}", org), |body| body.as_array())?;
+        Ok(repos.iter().map(|r| RepoInfo {
+            full_name: r["full_name"].as_str().unwrap_or("").to_string(),
+            fork: r["fork"].as_bool().unwrap_or(false),
+            archived: r["archived"].as_bool().unwrap_or(false),
+            can_push: r["permissions"]["push"].as_bool().unwrap_or(false),
+        }).collect())
+    }
+
+    pub fn list_deploy_keys(&self, repo_owner: &str, repo_name: &str) -> Result<Vec<DeployKeyInfo>, String> {
+        if self.token.starts_with("mock") {
+            return Ok(vec![]);
+        }
+
+        let url = format!("https://api.github.com/repos/{}/{}/keys", repo_owner, repo_name);
+        let keys = self.get_pages(&url, MAX_LIST_PAGES, "Failed to list deploy keys", |body| body.as_array())?;
+        Ok(keys.iter().map(|k| DeployKeyInfo {
+            id: k["id"].as_u64().unwrap_or(0),
+            title: k["title"].as_str().unwrap_or("").to_string(),
+        }).collect())
+    }
+
     pub fn remove_deploy_key(&self, repo_owner: &str, repo_name: &str, key_id: u64) -> Result<(), String> {
          if self.token.starts_with("mock") {
             println!("MOCK: Removing deploy key {} from {}/{}", key_id, repo_owner, repo_name);
@@ -166,15 +332,11 @@ impl GithubClient {
 
         // 1. Get SHA of file (if it exists)
         let url_get = format!("https://api.github.com/repos/{}/{}/contents/{}", repo_owner, repo_name, path);
-        let res_get = self.client.get(&url_get)
-             .bearer_auth(&self.token)
-             .send()
-             .map_err(|e| e.to_string())?;
+        let (status_get, body_get) = self.get_json(&url_get)?;
 
         let mut sha = None;
-        if res_get.status().is_success() {
-            let body: serde_json::Value = res_get.json().map_err(|e| e.to_string())?;
-            sha = body["sha"].as_str().map(|s| s.to_string());
+        if status_get.is_success() {
+            sha = body_get["sha"].as_str().map(|s| s.to_string());
         }
 
         // 2. Put file
@@ -202,29 +364,164 @@ impl GithubClient {
         Ok(())
     }
 
+    /// Login of the account the token belongs to, i.e. the owner of repos it creates.
+    pub fn get_authenticated_user(&self) -> Result<String, String> {
+        if self.token.starts_with("mock") {
+            return Ok("mock_user".to_string());
+        }
+        let (status, body) = self.get_json("https://api.github.com/user")?;
+        if !status.is_success() {
+            return Err(format!("Failed to get user: {}", status));
+        }
+        Ok(body["login"].as_str().unwrap_or("").to_string())
+    }
+
+    /// Contents of a file on the default branch, None if it doesn't exist.
+    pub fn get_file_content(&self, repo_owner: &str, repo_name: &str, path: &str) -> Result<Option<String>, String> {
+        if self.token.starts_with("mock") {
+            return Ok(None);
+        }
+        let url = format!("https://api.github.com/repos/{}/{}/contents/{}", repo_owner, repo_name, path);
+        let (status, body) = self.get_json(&url)?;
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(format!("Failed to get file: {}", status));
+        }
+        // Base64 wrapped at 60 columns
+        let encoded: String = body["content"].as_str().unwrap_or("").split_whitespace().collect();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+        String::from_utf8(bytes).map(Some).map_err(|_| format!("{} is not valid UTF-8", path))
+    }
+
     pub fn check_repo_access(&self, repo_owner: &str, repo_name: &str) -> Result<bool, String> {
         if self.token.starts_with("mock") {
              return Ok(true);
         }
         let url = format!("https://api.github.com/repos/{}/{}", repo_owner, repo_name);
-        let res = self.client.get(&url)
-            .bearer_auth(&self.token)
-            .send()
-            .map_err(|e| e.to_string())?;
+        let (status, _) = self.get_json(&url)?;
 
-        Ok(res.status().is_success())
+        Ok(status.is_success())
     }
 
-    pub fn merge_pull_request(&self, repo_owner: &str, repo_name: &str, pull_number: u64) -> Result<String, String> {
+    pub fn get_default_branch(&self, repo_owner: &str, repo_name: &str) -> Result<String, String> {
+        if self.token.starts_with("mock") {
+             return Ok("main".to_string());
+        }
+        let url = format!("https://api.github.com/repos/{}/{}", repo_owner, repo_name);
+        let (status, body) = self.get_json(&url)?;
+
+        if !status.is_success() {
+            return Err(format!("Failed to get repo: {}", status));
+        }
+        Ok(body["default_branch"].as_str().unwrap_or("main").to_string())
+    }
+
+    pub fn get_pull_request(&self, repo_owner: &str, repo_name: &str, pull_number: u64) -> Result<PullRequestInfo, String> {
+        if self.token.starts_with("mock") {
+            return Ok(PullRequestInfo { head_sha: "mock_sha".to_string(), open: true, merged: false, changed_lines: 42 });
+        }
+        let url = format!("https://api.github.com/repos/{}/{}/pulls/{}", repo_owner, repo_name, pull_number);
+        let (status, body) = self.get_json(&url)?;
+        if !status.is_success() {
+            return Err(format!("Failed to get PR: {}", status));
+        }
+        Ok(PullRequestInfo {
+            head_sha: body["head"]["sha"].as_str().unwrap_or("").to_string(),
+            open: body["state"].as_str() == Some("open"),
+            merged: body["merged"].as_bool().unwrap_or(false),
+            changed_lines: body["additions"].as_u64().unwrap_or(0) + body["deletions"].as_u64().unwrap_or(0),
+        })
+    }
+
+    pub fn list_pull_request_files(&self, repo_owner: &str, repo_name: &str, pull_number: u64) -> Result<Vec<String>, String> {
+        if self.token.starts_with("mock") {
+            return Ok(vec!["README.md".to_string(), "src/main.rs".to_string()]);
+        }
+        // GitHub stops listing at 3000 files
+        let url = format!("https://api.github.com/repos/{}/{}/pulls/{}/files", repo_owner, repo_name, pull_number);
+        let files = self.get_pages(&url, 30, "Failed to list PR files", |body| body.as_array())?;
+        Ok(files.iter().map(|f| f["filename"].as_str().unwrap_or("").to_string()).collect())
+    }
+
+    // Every item of a list that reports its total_count. Errs rather than
+    // returning part of the list when it runs past `MAX_CHECK_PAGES`.
+    fn get_counted(&self, url: &str, key: &str, error: &str) -> Result<Vec<serde_json::Value>, String> {
+        let mut all = vec![];
+        let mut total = 0;
+        for page in 1..=MAX_CHECK_PAGES {
+            let (status, body) = self.get_json(&format!("{}?per_page=100&page={}", url, page))?;
+            if !status.is_success() {
+                return Err(format!("{}: {}", error, status));
+            }
+            total = body["total_count"].as_u64().unwrap_or(0) as usize;
+            let page_items = body[key].as_array().cloned().unwrap_or_default();
+            let last_page = page_items.len() < 100;
+            all.extend(page_items);
+            if last_page || all.len() >= total {
+                break;
+            }
+        }
+        if all.len() < total {
+            return Err(format!("{}: only {} of {} could be read", error, all.len(), total));
+        }
+        Ok(all)
+    }
+
+    /// Combines check runs and commit statuses on `sha`.
+    pub fn get_checks(&self, repo_owner: &str, repo_name: &str, sha: &str) -> Result<ChecksState, String> {
+        if self.token.starts_with("mock") {
+            return Ok(ChecksState::Passed);
+        }
+        let mut pending = vec![];
+        let mut failed = vec![];
+
+        let url = format!("https://api.github.com/repos/{}/{}/commits/{}/check-runs", repo_owner, repo_name, sha);
+        let runs = self.get_counted(&url, "check_runs", "Failed to get check runs")?;
+        for run in &runs {
+            let name = run["name"].as_str().unwrap_or("").to_string();
+            match (run["status"].as_str(), run["conclusion"].as_str()) {
+                (Some("completed"), Some("success" | "neutral" | "skipped")) => {}
+                (Some("completed"), _) => failed.push(name),
+                _ => pending.push(name),
+            }
+        }
+
+        let url = format!("https://api.github.com/repos/{}/{}/commits/{}/status", repo_owner, repo_name, sha);
+        let statuses = self.get_counted(&url, "statuses", "Failed to get commit status")?;
+        for s in &statuses {
+            let name = s["context"].as_str().unwrap_or("").to_string();
+            match s["state"].as_str() {
+                Some("success") => {}
+                Some("pending") => pending.push(name),
+                _ => failed.push(name),
+            }
+        }
+
+        if runs.is_empty() && statuses.is_empty() {
+            return Ok(ChecksState::NoChecks);
+        }
+        Ok(match (failed.is_empty(), pending.is_empty()) {
+            (false, _) => ChecksState::Failed(failed),
+            (true, false) => ChecksState::Pending(pending),
+            (true, true) => ChecksState::Passed,
+        })
+    }
+
+    /// With `sha`, GitHub refuses the merge if the PR's head has moved on since.
+    pub fn merge_pull_request(&self, repo_owner: &str, repo_name: &str, pull_number: u64, method: MergeMethod, sha: Option<&str>) -> Result<String, String> {
         if self.token.starts_with("mock") {
              return Ok("mock_sha_merged".to_string());
         }
         let url = format!("https://api.github.com/repos/{}/{}/pulls/{}/merge", repo_owner, repo_name, pull_number);
+        let mut body = json!({ "merge_method": method });
+        if let Some(sha) = sha {
+            body["sha"] = json!(sha);
+        }
         let res = self.client.put(&url)
             .bearer_auth(&self.token)
-            .json(&json!({
-                "merge_method": "squash"
-            }))
+            .json(&body)
             .send()
             .map_err(|e| e.to_string())?;
 
@@ -234,3 +531,9 @@ impl GithubClient {
         Ok("merged".to_string())
     }
 }
+
+fn header_string(res: &Response, name: HeaderName) -> Option<String> {
+    res.headers().get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
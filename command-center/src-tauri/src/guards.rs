@@ -0,0 +1,93 @@
+use crate::github::GithubClient;
+use crate::poller;
+
+// Ownership of provisioned GitHub resources. A guard deletes its resource when
+// released, or when dropped without being released: on an early return, a
+// panic unwinding through the job, or a cancelled pipeline. Failures to clean
+// up are recorded as job warnings so the user knows something was left behind.
+
+pub struct CodespaceGuard {
+    gh: GithubClient,
+    app: tauri::AppHandle,
+    job_id: String,
+    name: Option<String>,
+}
+
+impl CodespaceGuard {
+    pub fn new(gh: &GithubClient, app: &tauri::AppHandle, job_id: &str, name: String) -> Self {
+        CodespaceGuard { gh: gh.clone(), app: app.clone(), job_id: job_id.to_string(), name: Some(name) }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("")
+    }
+
+    /// Deletes the codespace now. Returns a log line; failures are also recorded as warnings.
+    pub fn release(mut self) -> String {
+        self.cleanup()
+    }
+
+    fn cleanup(&mut self) -> String {
+        let Some(name) = self.name.take() else { return String::new() };
+        match self.gh.delete_codespace(&name) {
+            Ok(()) => "Deleted codespace.".to_string(),
+            Err(e) => {
+                let warning = format!("Failed to delete codespace {}: {}", name, e);
+                poller::emit_warning(&self.app, &self.job_id, &warning);
+                warning
+            }
+        }
+    }
+}
+
+impl Drop for CodespaceGuard {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
+
+pub struct DeployKeyGuard {
+    gh: GithubClient,
+    app: tauri::AppHandle,
+    job_id: String,
+    owner: String,
+    repo: String,
+    key_id: Option<u64>,
+}
+
+impl DeployKeyGuard {
+    pub fn new(gh: &GithubClient, app: &tauri::AppHandle, job_id: &str, owner: &str, repo: &str, key_id: u64) -> Self {
+        DeployKeyGuard {
+            gh: gh.clone(),
+            app: app.clone(),
+            job_id: job_id.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            key_id: Some(key_id),
+        }
+    }
+
+    /// Removes the key now. Returns a log line; failures are also recorded as warnings.
+    pub fn release(mut self) -> String {
+        self.cleanup()
+    }
+
+    fn cleanup(&mut self) -> String {
+        let Some(id) = self.key_id.take() else { return String::new() };
+        match self.gh.remove_deploy_key(&self.owner, &self.repo, id) {
+            Ok(()) => "Removed deploy key.".to_string(),
+            Err(e) => {
+                // Still has write access to the repo, so this one matters
+                let warning = format!("Failed to remove deploy key {} from {}/{}: {}", id, self.owner, self.repo, e);
+                poller::emit_warning(&self.app, &self.job_id, &warning);
+                warning
+            }
+        }
+    }
+}
+
+impl Drop for DeployKeyGuard {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Conditional request cache for GitHub GETs.
+// Entries are keyed by URL and replayed when the server answers 304 Not Modified,
+// which GitHub does not count against the rate limit. Changes are written to
+// disk by flush(), on a timer and at exit, never by the request that made them.
+
+pub const DEFAULT_MAX_ENTRIES: usize = 256;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: serde_json::Value,
+    last_used: u64, // Unix millis, bumped so every use is distinct
+}
+
+impl CacheEntry {
+    pub fn new(etag: Option<String>, last_modified: Option<String>, body: serde_json::Value) -> Self {
+        CacheEntry { etag, last_modified, body, last_used: 0 }
+    }
+}
+
+pub struct ResponseCache {
+    entries: HashMap<String, CacheEntry>,
+    max_entries: usize,
+    path: Option<PathBuf>, // None = memory only
+    dirty: bool, // Changed since the last flush
+    clock: u64, // Latest last_used handed out
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize) -> Self {
+        ResponseCache {
+            entries: HashMap::new(),
+            max_entries,
+            path: None,
+            dirty: false,
+            clock: 0,
+        }
+    }
+
+    /// Binds the cache to a file and loads whatever survived the last run.
+    pub fn attach(&mut self, path: PathBuf) {
+        if let Ok(raw) = fs::read_to_string(&path) {
+            match serde_json::from_str::<HashMap<String, CacheEntry>>(&raw) {
+                Ok(entries) => self.entries = entries,
+                Err(e) => println!("Discarding unreadable HTTP cache: {}", e),
+            }
+        }
+        self.clock = self.entries.values().map(|e| e.last_used).max().unwrap_or(0);
+        self.path = Some(path);
+        self.evict();
+    }
+
+    // Two uses in the same millisecond still get an order
+    fn tick(&mut self) -> u64 {
+        self.clock = now().max(self.clock + 1);
+        self.clock
+    }
+
+    pub fn get(&mut self, url: &str) -> Option<CacheEntry> {
+        let used = self.tick();
+        let entry = self.entries.get_mut(url)?;
+        entry.last_used = used;
+        Some(entry.clone())
+    }
+
+    pub fn insert(&mut self, url: &str, mut entry: CacheEntry) {
+        entry.last_used = self.tick();
+        self.entries.insert(url.to_string(), entry);
+        self.evict();
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, url: &str) {
+        if self.entries.remove(url).is_some() {
+            self.dirty = true;
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.max_entries {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(k) => { self.entries.remove(&k); }
+                None => break,
+            }
+        }
+    }
+
+    // The file and what to write to it, if anything changed since the last call
+    fn snapshot(&mut self) -> Option<(PathBuf, String)> {
+        let path = self.path.clone().filter(|_| self.dirty)?;
+        self.dirty = false;
+        match serde_json::to_string(&self.entries) {
+            Ok(raw) => Some((path, raw)),
+            Err(e) => {
+                println!("Failed to serialize HTTP cache: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn write(path: &Path, raw: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, raw).map_err(|e| e.to_string())
+}
+
+/// Writes the cache to its file if it changed. The lock is only held while
+/// serializing, so requests aren't kept waiting on the disk.
+pub fn flush(cache: &Mutex<ResponseCache>) {
+    let Some((path, raw)) = cache.lock().unwrap().snapshot() else { return };
+    if let Err(e) = write(&path, &raw) {
+        println!("Failed to persist HTTP cache: {}", e);
+        cache.lock().unwrap().dirty = true;
+    }
+}
+
+/// Flushes every FLUSH_INTERVAL for as long as the app runs.
+pub fn start_flusher(cache: Arc<Mutex<ResponseCache>>) {
+    thread::spawn(move || loop {
+        thread::sleep(FLUSH_INTERVAL);
+        flush(&cache);
+    });
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(etag: &str) -> CacheEntry {
+        CacheEntry::new(Some(etag.to_string()), None, json!({ "etag": etag }))
+    }
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("http-cache-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn the_least_recently_used_entry_is_evicted_first() {
+        let mut cache = ResponseCache::new(2);
+        cache.insert("a", entry("1"));
+        cache.insert("b", entry("2"));
+        assert!(cache.get("a").is_some()); // b is now the oldest
+        cache.insert("c", entry("3"));
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().etag.as_deref(), Some("1"));
+        assert_eq!(cache.get("c").unwrap().etag.as_deref(), Some("3"));
+        assert_eq!(cache.entries.len(), 2);
+    }
+
+    #[test]
+    fn inserting_an_existing_url_replaces_it() {
+        let mut cache = ResponseCache::new(2);
+        cache.insert("a", entry("1"));
+        cache.insert("a", entry("2"));
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.get("a").unwrap().body, json!({ "etag": "2" }));
+    }
+
+    #[test]
+    fn only_changes_are_snapshotted() {
+        let mut cache = ResponseCache::new(4);
+        cache.insert("a", entry("1"));
+        assert!(cache.snapshot().is_none(), "a memory-only cache has nowhere to write");
+
+        let path = temp_path();
+        cache.attach(path.clone());
+        assert!(cache.snapshot().is_some());
+        assert!(cache.snapshot().is_none());
+        cache.get("a");
+        assert!(cache.snapshot().is_none(), "reads don't need a write");
+        cache.remove("missing");
+        assert!(cache.snapshot().is_none());
+        cache.remove("a");
+        assert!(cache.snapshot().is_some());
+    }
+
+    #[test]
+    fn a_flushed_cache_loads_back_in_lru_order() {
+        let path = temp_path();
+        let cache = Mutex::new(ResponseCache::new(4));
+        {
+            let mut cache = cache.lock().unwrap();
+            cache.attach(path.clone());
+            cache.insert("a", CacheEntry::new(Some("\"e1\"".to_string()), Some("Mon".to_string()), json!([1])));
+            cache.insert("b", entry("2"));
+            cache.insert("c", entry("3"));
+            cache.get("a");
+        }
+        flush(&cache);
+
+        let mut loaded = ResponseCache::new(4);
+        loaded.attach(path.clone());
+        let a = loaded.get("a").unwrap();
+        assert_eq!((a.etag.as_deref(), a.last_modified.as_deref(), a.body), (Some("\"e1\""), Some("Mon"), json!([1])));
+
+        // A smaller bound drops the oldest on load, and new uses rank after loaded ones
+        let mut small = ResponseCache::new(2);
+        small.attach(path.clone());
+        assert!(small.get("b").is_none());
+        small.insert("d", entry("4"));
+        assert!(small.get("c").is_none());
+        assert!(small.get("a").is_some() && small.get("d").is_some());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn an_unreadable_file_starts_empty() {
+        let path = temp_path();
+        fs::write(&path, "not json").unwrap();
+        let mut cache = ResponseCache::new(4);
+        cache.attach(path.clone());
+        assert!(cache.entries.is_empty());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,448 @@
+use crate::types::{JobState, StatusChange};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+// Durable home of every job. Each change is appended to a JSON-lines file in the
+// app data dir; on startup the file is replayed, migrated and compacted to one
+// record per job. Jobs and their logs are kept apart so a log line doesn't
+// rewrite the whole job.
+
+pub const JOBS_FILE: &str = "jobs.jsonl";
+
+const SCHEMA_VERSION: u32 = 1;
+
+// MIGRATIONS[n] upgrades a job record written at schema version n + 1 to n + 2.
+// Add an entry here, and bump SCHEMA_VERSION, whenever JobState changes in a way
+// serde defaults can't absorb (renamed or re-typed fields).
+const MIGRATIONS: &[fn(&mut serde_json::Value)] = &[];
+const _: () = assert!(SCHEMA_VERSION as usize == MIGRATIONS.len() + 1);
+
+// Rewrite the file once this many records pile up since the last compaction
+const COMPACT_AFTER: usize = 5000;
+const MAX_LOG_LINES: usize = 2000; // Per job, oldest dropped first
+const MAX_PAGE: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", rename_all_fields = "camelCase")]
+enum Record {
+    Meta { schema_version: u32 },
+    Put { job: serde_json::Value },
+    Logs { id: String, lines: Vec<String> },
+}
+
+/// Filters for `query`. Every field is optional; an empty query returns the newest page.
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JobQuery {
+    pub statuses: Vec<String>,       // Status keys, e.g. "pr_ready". Empty = any
+    pub repo: Option<String>,        // Case-insensitive substring of owner/repo
+    pub kind: Option<String>,        // "scaffold", "uplink" or "import"
+    pub batch_id: Option<String>,
+    pub schedule_id: Option<String>,
+    pub created_after: Option<u64>,  // Unix seconds, inclusive
+    pub created_before: Option<u64>, // Unix seconds, exclusive
+    pub offset: usize,
+    pub limit: Option<usize>,        // Defaults to, and is capped at, MAX_PAGE
+}
+
+impl JobQuery {
+    fn matches(&self, job: &JobState) -> bool {
+        (self.statuses.is_empty() || self.statuses.iter().any(|s| s == job.status.key()))
+            && self.repo.as_ref().is_none_or(|repo| job.github_repo.to_lowercase().contains(&repo.to_lowercase()))
+            && self.kind.as_ref().is_none_or(|kind| job.params.as_ref().is_some_and(|p| p.kind() == kind))
+            && self.batch_id.as_ref().is_none_or(|id| job.batch_id.as_ref() == Some(id))
+            && self.schedule_id.as_ref().is_none_or(|id| job.schedule_id.as_ref() == Some(id))
+            && self.created_after.is_none_or(|t| job.created_at >= t)
+            && self.created_before.is_none_or(|t| job.created_at < t)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobPage {
+    pub jobs: Vec<JobState>,
+    pub total: usize, // Matching jobs across all pages
+}
+
+struct Inner {
+    jobs: HashMap<String, JobState>,
+    logs: HashMap<String, Vec<String>>,
+    path: Option<PathBuf>,
+    file: Option<File>,
+    records: usize,
+}
+
+pub struct JobStore {
+    inner: Mutex<Inner>,
+}
+
+impl JobStore {
+    pub fn memory() -> Self {
+        JobStore {
+            inner: Mutex::new(Inner {
+                jobs: HashMap::new(),
+                logs: HashMap::new(),
+                path: None,
+                file: None,
+                records: 0,
+            }),
+        }
+    }
+
+    /// Loads the store at `path`. A file that can't be used is left untouched
+    /// and the store runs in memory for this session.
+    pub fn open(path: PathBuf) -> Self {
+        let store = JobStore::memory();
+        match load(&path) {
+            Ok((jobs, logs)) => {
+                let mut inner = store.lock();
+                inner.jobs = jobs;
+                inner.logs = logs;
+                inner.path = Some(path);
+                if let Err(e) = inner.compact() {
+                    println!("Failed to compact job store, jobs won't be saved: {}", e);
+                    inner.file = None;
+                }
+            }
+            Err(e) => println!("Job store unavailable, jobs won't be saved: {}", e),
+        }
+        store
+    }
+
+    // Writers never panic while holding the lock, but a panicking job thread might;
+    // the data is still consistent, so keep going rather than poison every command.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobState> {
+        self.lock().jobs.get(id).cloned()
+    }
+
+    pub fn all(&self) -> Vec<JobState> {
+        self.lock().jobs.values().cloned().collect()
+    }
+
+    pub fn find(&self, predicate: impl Fn(&JobState) -> bool) -> Option<JobState> {
+        self.lock().jobs.values().find(|job| predicate(job)).cloned()
+    }
+
+    pub fn insert(&self, mut job: JobState) {
+        if job.history.is_empty() {
+            job.history.push(StatusChange::now(job.status.clone()));
+        }
+        let mut inner = self.lock();
+        inner.put(&job);
+        inner.jobs.insert(job.id.clone(), job);
+    }
+
+    /// Applies `f` and persists the result. Status changes are added to the history.
+    pub fn update<T>(&self, id: &str, f: impl FnOnce(&mut JobState) -> T) -> Option<T> {
+        let mut inner = self.lock();
+        let job = inner.jobs.get_mut(id)?;
+        let before = job.status.clone();
+        let result = f(job);
+        if job.status != before {
+            job.history.push(StatusChange::now(job.status.clone()));
+        }
+        let job = job.clone();
+        inner.put(&job);
+        Some(result)
+    }
+
+    /// Matching jobs, newest first.
+    pub fn query(&self, query: &JobQuery) -> JobPage {
+        let inner = self.lock();
+        let mut jobs: Vec<&JobState> = inner.jobs.values().filter(|job| query.matches(job)).collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        let limit = query.limit.unwrap_or(MAX_PAGE).min(MAX_PAGE);
+        JobPage {
+            total: jobs.len(),
+            jobs: jobs.into_iter().skip(query.offset).take(limit).cloned().collect(),
+        }
+    }
+
+    pub fn append_logs(&self, id: &str, lines: &[String]) {
+        if lines.is_empty() {
+            return;
+        }
+        let mut inner = self.lock();
+        if !inner.jobs.contains_key(id) {
+            return;
+        }
+        let log = inner.logs.entry(id.to_string()).or_default();
+        log.extend(lines.iter().cloned());
+        if log.len() > MAX_LOG_LINES {
+            let excess = log.len() - MAX_LOG_LINES;
+            log.drain(..excess);
+        }
+        inner.append(&Record::Logs { id: id.to_string(), lines: lines.to_vec() });
+    }
+
+    /// None if the job doesn't exist.
+    pub fn logs(&self, id: &str) -> Option<Vec<String>> {
+        let inner = self.lock();
+        inner.jobs.contains_key(id).then(|| inner.logs.get(id).cloned().unwrap_or_default())
+    }
+}
+
+impl Inner {
+    fn put(&mut self, job: &JobState) {
+        match serde_json::to_value(job) {
+            Ok(job) => self.append(&Record::Put { job }),
+            Err(e) => println!("Failed to serialize job: {}", e),
+        }
+    }
+
+    fn append(&mut self, record: &Record) {
+        let Some(file) = self.file.as_mut() else { return };
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => return println!("Failed to serialize job record: {}", e),
+        };
+        if let Err(e) = writeln!(file, "{}", line) {
+            println!("Failed to write job store: {}", e);
+            return;
+        }
+        self.records += 1;
+        if self.records > COMPACT_AFTER {
+            if let Err(e) = self.compact() {
+                println!("Failed to compact job store: {}", e);
+            }
+        }
+    }
+
+    // Writes the current state to a fresh file and swaps it in.
+    fn compact(&mut self) -> Result<(), String> {
+        let Some(path) = self.path.clone() else { return Ok(()) };
+        let tmp = path.with_extension("jsonl.tmp");
+        {
+            let mut out = File::create(&tmp).map_err(|e| e.to_string())?;
+            let mut write = |record: &Record| -> Result<(), String> {
+                let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+                writeln!(out, "{}", line).map_err(|e| e.to_string())
+            };
+            write(&Record::Meta { schema_version: SCHEMA_VERSION })?;
+            for job in self.jobs.values() {
+                write(&Record::Put { job: serde_json::to_value(job).map_err(|e| e.to_string())? })?;
+            }
+            for (id, lines) in &self.logs {
+                write(&Record::Logs { id: id.clone(), lines: lines.clone() })?;
+            }
+            out.sync_all().map_err(|e| e.to_string())?;
+        }
+        fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+
+        self.file = Some(OpenOptions::new().append(true).open(&path).map_err(|e| e.to_string())?);
+        self.records = 0;
+        Ok(())
+    }
+}
+
+// Brings a job record written at `version` up to date. `version` is at least 1.
+fn migrate(job: &mut serde_json::Value, version: u32, migrations: &[fn(&mut serde_json::Value)]) {
+    for migration in migrations.iter().skip(version as usize - 1) {
+        migration(job);
+    }
+}
+
+type Loaded = (HashMap<String, JobState>, HashMap<String, Vec<String>>);
+
+fn load(path: &Path) -> Result<Loaded, String> {
+    let mut jobs = HashMap::new();
+    let mut logs: HashMap<String, Vec<String>> = HashMap::new();
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((jobs, logs)),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    // A file without a meta record predates versioning
+    let mut version = 1;
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The last line may be cut short if the app died mid-write
+        let record: Record = match serde_json::from_str(&line) {
+            Ok(record) => record,
+            Err(e) => {
+                println!("Skipping unreadable job store line {}: {}", n + 1, e);
+                continue;
+            }
+        };
+        match record {
+            Record::Meta { schema_version } => {
+                if schema_version > SCHEMA_VERSION {
+                    return Err(format!(
+                        "{} was written by a newer version of the app (schema {})",
+                        path.display(), schema_version
+                    ));
+                }
+                if schema_version < 1 {
+                    return Err(format!("{} has an invalid schema version {}", path.display(), schema_version));
+                }
+                version = schema_version;
+            }
+            Record::Put { mut job } => {
+                migrate(&mut job, version, MIGRATIONS);
+                match serde_json::from_value::<JobState>(job) {
+                    Ok(job) => { jobs.insert(job.id.clone(), job); }
+                    Err(e) => println!("Skipping unreadable job on line {}: {}", n + 1, e),
+                }
+            }
+            Record::Logs { id, lines } => logs.entry(id).or_default().extend(lines),
+        }
+    }
+    logs.retain(|id, _| jobs.contains_key(id));
+    for log in logs.values_mut() {
+        if log.len() > MAX_LOG_LINES {
+            let excess = log.len() - MAX_LOG_LINES;
+            log.drain(..excess);
+        }
+    }
+    Ok((jobs, logs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AgentMode, JobParams, JobStatus};
+
+    // A fresh path per test; removed again on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new() -> Self {
+            TempFile(std::env::temp_dir().join(format!("job-store-{}.jsonl", uuid::Uuid::new_v4())))
+        }
+
+        fn write(&self, lines: &[serde_json::Value]) {
+            let text: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+            fs::write(&self.0, text).unwrap();
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            let _ = fs::remove_file(self.0.with_extension("jsonl.tmp"));
+        }
+    }
+
+    fn job(id: &str) -> JobState {
+        JobState::new(id.to_string(), "owner/repo".to_string(), JobStatus::Planning, JobParams::Uplink {
+            repo_url: "https://github.com/owner/repo".to_string(),
+            context: String::new(),
+            mode: AgentMode::Auto,
+            prompt: None,
+            session: Default::default(),
+        })
+    }
+
+    fn put(job: &JobState) -> serde_json::Value {
+        serde_json::json!({ "op": "put", "job": job })
+    }
+
+    #[test]
+    fn a_missing_file_loads_empty() {
+        let file = TempFile::new();
+        let (jobs, logs) = load(&file.0).unwrap();
+        assert!(jobs.is_empty() && logs.is_empty());
+    }
+
+    #[test]
+    fn jobs_and_logs_survive_a_reopen() {
+        let file = TempFile::new();
+        let store = JobStore::open(file.0.clone());
+        store.insert(job("a"));
+        store.update("a", |job| job.status = JobStatus::Working);
+        store.append_logs("a", &["one".to_string(), "two".to_string()]);
+        drop(store);
+
+        let store = JobStore::open(file.0.clone());
+        let a = store.get("a").unwrap();
+        assert_eq!(a.status, JobStatus::Working);
+        assert_eq!(a.history.len(), 2);
+        assert_eq!(store.logs("a").unwrap(), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn a_file_without_meta_is_read_as_version_1() {
+        let file = TempFile::new();
+        file.write(&[put(&job("a"))]);
+        let (jobs, _) = load(&file.0).unwrap();
+        assert!(jobs.contains_key("a"));
+    }
+
+    #[test]
+    fn unreadable_lines_are_skipped() {
+        let file = TempFile::new();
+        file.write(&[put(&job("a")), serde_json::json!({ "op": "put", "job": { "id": "b" } })]);
+        let mut text = fs::read_to_string(&file.0).unwrap();
+        text.push_str("{\"op\":\"logs\",\"id\":\"a\",\"li");
+        fs::write(&file.0, text).unwrap();
+
+        let (jobs, logs) = load(&file.0).unwrap();
+        assert_eq!(jobs.keys().collect::<Vec<_>>(), vec!["a"]);
+        assert!(logs.is_empty());
+    }
+
+    #[test]
+    fn logs_of_unknown_jobs_are_dropped() {
+        let file = TempFile::new();
+        file.write(&[
+            put(&job("a")),
+            serde_json::json!({ "op": "logs", "id": "a", "lines": ["kept"] }),
+            serde_json::json!({ "op": "logs", "id": "gone", "lines": ["dropped"] }),
+        ]);
+        let (_, logs) = load(&file.0).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs["a"], vec!["kept"]);
+    }
+
+    #[test]
+    fn schema_versions_out_of_range_are_rejected() {
+        for version in [0, SCHEMA_VERSION + 1] {
+            let file = TempFile::new();
+            file.write(&[serde_json::json!({ "op": "meta", "schemaVersion": version }), put(&job("a"))]);
+            assert!(load(&file.0).is_err(), "schema {} was accepted", version);
+
+            // The store runs in memory and leaves the file for a version that can read it
+            let before = fs::read_to_string(&file.0).unwrap();
+            let store = JobStore::open(file.0.clone());
+            assert!(store.get("a").is_none());
+            store.insert(job("b"));
+            assert_eq!(fs::read_to_string(&file.0).unwrap(), before);
+        }
+    }
+
+    #[test]
+    fn migrations_run_from_the_record_version_on() {
+        fn rename_repo(job: &mut serde_json::Value) {
+            job["githubRepo"] = job["repo"].take();
+        }
+        fn add_suffix(job: &mut serde_json::Value) {
+            let repo = format!("{}-v3", job["githubRepo"].as_str().unwrap());
+            job["githubRepo"] = repo.into();
+        }
+        let migrations: &[fn(&mut serde_json::Value)] = &[rename_repo, add_suffix];
+
+        let mut v1 = serde_json::json!({ "repo": "owner/repo" });
+        migrate(&mut v1, 1, migrations);
+        assert_eq!(v1["githubRepo"], "owner/repo-v3");
+
+        let mut v2 = serde_json::json!({ "githubRepo": "owner/repo" });
+        migrate(&mut v2, 2, migrations);
+        assert_eq!(v2["githubRepo"], "owner/repo-v3");
+
+        let mut v3 = serde_json::json!({ "githubRepo": "owner/repo" });
+        migrate(&mut v3, 3, migrations);
+        assert_eq!(v3["githubRepo"], "owner/repo");
+    }
+}
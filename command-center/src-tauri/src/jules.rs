@@ -1,119 +1,702 @@
-use reqwest::blocking::Client;
-use serde_json::json;
-use crate::types::{JobStatus, PrDetails};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::types::{Activity, ActivityKind, Artifact, JobStatus, Plan, PlanStep, PrDetails};
+
+/// Session lifecycle as reported by the Jules API.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionState {
+    Queued,
+    Planning,
+    AwaitingPlanApproval,
+    AwaitingUserFeedback,
+    InProgress,
+    Paused,
+    Failed,
+    Completed,
+    #[serde(other)]
+    StateUnspecified,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub name: String, // sessions/{id}
+    pub title: String,
+    pub state: SessionState,
+    pub repo: Option<String>, // owner/repo
+    pub url: Option<String>,
+    pub pull_request: Option<PrDetails>,
+}
+
+impl SessionSummary {
+    /// Map Jules State to App JobStatus
+    pub fn job_status(&self) -> JobStatus {
+        match self.state {
+            SessionState::StateUnspecified
+            | SessionState::Queued
+            | SessionState::Planning => JobStatus::Planning,
+            SessionState::AwaitingPlanApproval => JobStatus::WaitingApproval,
+            SessionState::AwaitingUserFeedback => JobStatus::WaitingFeedback,
+            SessionState::InProgress => JobStatus::Working,
+            SessionState::Paused => JobStatus::Paused,
+            SessionState::Failed => JobStatus::Failed { reason: "Jules session failed".to_string() },
+            SessionState::Completed if self.pull_request.is_some() => JobStatus::PrReady,
+            SessionState::Completed => JobStatus::Completed,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    pub name: String, // sources/github/{owner}/{repo}
+    pub owner: String,
+    pub repo: String,
+}
+
+/// Whether Jules can work on a repo. Checked before a job is created.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum SourceResolution {
+    Connected { source: String },
+    NotInstalled { repo: String, install_url: String },
+}
+
+pub const JULES_INSTALL_URL: &str = "https://github.com/apps/google-labs-jules/installations/new";
+
+const DEFAULT_BASE_URL: &str = "https://jules.googleapis.com/v1";
+
+#[derive(Clone, Debug)]
+pub enum JulesAuth {
+    Bearer(String), // Google OAuth token
+    ApiKey(String), // Sent as X-Goog-Api-Key
+}
+
+/// Where and how to reach Jules. Plain data, so commands can build it and move it
+/// into spawn_blocking, where the blocking client has to be created.
+#[derive(Clone, Debug)]
+pub struct JulesConfig {
+    pub base_url: Option<String>, // None = production, or the mock server for mock credentials
+    pub auth: JulesAuth,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AutomationMode {
+    AutomationModeUnspecified, // Changes stay on the session, no PR
+    AutoCreatePr,
+}
+
+/// Optional settings for a new session.
+#[derive(Clone, Debug)]
+pub struct SessionOptions {
+    pub title: Option<String>,
+    pub starting_branch: Option<String>,
+    pub automation_mode: AutomationMode,
+    pub require_plan_approval: bool,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        SessionOptions {
+            title: None,
+            starting_branch: None,
+            automation_mode: AutomationMode::AutoCreatePr, // Jobs end in a PR
+            require_plan_approval: false,
+        }
+    }
+}
+
+// Wire types. Field names follow the Jules REST resources.
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SourceContext {
+    source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    github_repo_context: Option<GithubRepoContext>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GithubRepoContext {
+    #[serde(default)]
+    starting_branch: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSessionRequest<'a> {
+    prompt: &'a str,
+    source_context: SourceContext,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    require_plan_approval: bool,
+    automation_mode: AutomationMode,
+}
+
+fn create_session_request<'a>(source: &str, prompt: &'a str, options: &'a SessionOptions) -> CreateSessionRequest<'a> {
+    CreateSessionRequest {
+        prompt,
+        source_context: SourceContext {
+            source: source.to_string(),
+            github_repo_context: options.starting_branch.clone()
+                .map(|starting_branch| GithubRepoContext { starting_branch }),
+        },
+        title: options.title.as_deref(),
+        require_plan_approval: options.require_plan_approval,
+        automation_mode: options.automation_mode,
+    }
+}
+
+/// The body start_session would send, for dry runs.
+pub fn session_request_body(source: &str, prompt: &str, options: &SessionOptions) -> serde_json::Value {
+    serde_json::to_value(create_session_request(source, prompt, options)).unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct SendMessageRequest<'a> {
+    prompt: &'a str,
+}
+
+// Empty request bodies, and marker payloads like sessionCompleted
+#[derive(Serialize, Deserialize, Default)]
+struct Empty {}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SessionResource {
+    name: String,
+    title: String,
+    state: Option<SessionState>,
+    url: Option<String>,
+    source_context: SourceContext,
+    source: Option<String>, // Older responses put the source at the top level
+    outputs: Vec<SessionOutput>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SessionOutput {
+    pull_request: Option<PullRequestOutput>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PullRequestOutput {
+    url: String,
+    title: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SourceResource {
+    name: String,
+    github_repo: GithubRepo,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct GithubRepo {
+    owner: String,
+    repo: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ActivityResource {
+    id: String,
+    create_time: String,
+    originator: String,
+    plan_generated: Option<PlanGenerated>,
+    plan_approved: Option<PlanApproved>,
+    progress_updated: Option<ProgressUpdated>,
+    agent_messaged: Option<AgentMessaged>,
+    user_messaged: Option<UserMessaged>,
+    session_completed: Option<Empty>,
+    session_failed: Option<SessionFailed>,
+    artifacts: Vec<ArtifactResource>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PlanGenerated {
+    plan: PlanResource,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct PlanResource {
+    id: String,
+    steps: Vec<PlanStepResource>,
+    create_time: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PlanStepResource {
+    index: Option<u32>,
+    title: String,
+    description: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct PlanApproved {
+    plan_id: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProgressUpdated {
+    title: String,
+    description: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct AgentMessaged {
+    agent_message: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct UserMessaged {
+    user_message: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SessionFailed {
+    reason: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ArtifactResource {
+    bash_output: Option<BashOutput>,
+    change_set: Option<ChangeSet>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct BashOutput {
+    command: String,
+    output: String,
+    exit_code: i64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ChangeSet {
+    git_patch: GitPatch,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GitPatch {
+    unidiff_patch: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ListSessionsResponse {
+    sessions: Vec<SessionResource>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ListSourcesResponse {
+    sources: Vec<SourceResource>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct ListActivitiesResponse {
+    activities: Vec<ActivityResource>,
+    next_page_token: Option<String>,
+}
 
 pub struct JulesClient {
-    token: String,
+    auth: JulesAuth,
+    base_url: String,
     client: Client,
 }
 
 impl JulesClient {
-    pub fn new(token: String) -> Self {
+    pub fn new(config: JulesConfig) -> Self {
+        let secret = match &config.auth {
+            JulesAuth::Bearer(token) | JulesAuth::ApiKey(token) => token,
+        };
+        // Mock credentials talk to the in-process scripted server instead of the real API
+        let base_url = match config.base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None if secret.starts_with("mock") => crate::mock_jules::demo_base_url(),
+            None => DEFAULT_BASE_URL.to_string(),
+        };
         JulesClient {
-            token,
+            auth: config.auth,
+            base_url,
             client: Client::builder().build().unwrap_or_default(),
         }
     }
 
-    pub fn start_session(&self, source: &str, prompt: &str, require_approval: bool) -> Result<String, String> {
-        if self.token.starts_with("mock") {
-            // Use system time for unique ID instead of function pointer
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            return Ok(format!("mock_session_{}", timestamp));
+    fn authed(&self, req: RequestBuilder) -> RequestBuilder {
+        match &self.auth {
+            JulesAuth::Bearer(token) => req.bearer_auth(token),
+            JulesAuth::ApiKey(key) => req.header("X-Goog-Api-Key", key),
+        }
+    }
+
+    fn send(&self, req: RequestBuilder) -> Result<Response, String> {
+        self.authed(req).send().map_err(|e| e.to_string())
+    }
+
+    fn read<T: DeserializeOwned>(res: Response, what: &str) -> Result<T, String> {
+        if !res.status().is_success() {
+             return Err(format!("Jules API Error ({}): {}", what, res.status()));
+        }
+        res.json().map_err(|e| e.to_string())
+    }
+
+    fn get<T: DeserializeOwned>(&self, url: &str, what: &str) -> Result<T, String> {
+        Self::read(self.send(self.client.get(url))?, what)
+    }
+
+    fn page_url(&self, path: &str, page_token: Option<&str>) -> Result<String, String> {
+        let mut url = reqwest::Url::parse(&format!("{}/{}", self.base_url, path)).map_err(|e| e.to_string())?;
+        url.query_pairs_mut().append_pair("pageSize", "100");
+        if let Some(token) = page_token {
+            url.query_pairs_mut().append_pair("pageToken", token);
+        }
+        Ok(url.to_string())
+    }
+
+    // Calls `page` with each response; it returns the next page token, if any.
+    fn paginate<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        what: &str,
+        max_pages: usize,
+        mut page: impl FnMut(T) -> Option<String>
+    ) -> Result<(), String> {
+        let mut page_token: Option<String> = None;
+        for _ in 0..max_pages {
+            let url = self.page_url(path, page_token.as_deref())?;
+            page_token = page(self.get(&url, what)?).filter(|t| !t.is_empty());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a session on `source` (a "sources/github/..." name) and returns its name.
+    pub fn start_session(&self, source: &str, prompt: &str, options: &SessionOptions) -> Result<String, String> {
+        let url = format!("{}/sessions", self.base_url);
+        let request = create_session_request(source, prompt, options);
+        let res = self.send(self.client.post(&url).json(&request))?;
+        if res.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err("Jules task limit reached. Lower the configured limits or try again later.".to_string());
+        }
+        let session: SessionResource = Self::read(res, "Create Session")?;
+        Ok(session.name)
+    }
+
+    pub fn get_session(&self, session_id: &str) -> Result<SessionSummary, String> {
+        let url = format!("{}/{}", self.base_url, session_id);
+        let session: SessionResource = self.get(&url, "Get Session")?;
+        Ok(session.into())
+    }
+
+    /// All sessions visible to the account, including ones started outside the app.
+    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>, String> {
+        let mut sessions = vec![];
+        self.paginate("sessions", "List Sessions", MAX_SESSION_PAGES, |page: ListSessionsResponse| {
+            sessions.extend(page.sessions.into_iter().map(SessionSummary::from));
+            page.next_page_token
+        })?;
+        Ok(sessions)
+    }
+
+    /// Repos connected to the account through the Jules GitHub app.
+    pub fn list_sources(&self) -> Result<Vec<Source>, String> {
+        let mut sources = vec![];
+        self.paginate("sources", "List Sources", MAX_SESSION_PAGES, |page: ListSourcesResponse| {
+            sources.extend(page.sources.into_iter().map(Source::from));
+            page.next_page_token
+        })?;
+        Ok(sources)
+    }
+
+    /// Maps a GitHub repo to its Jules source name, or reports that the app isn't installed on it.
+    pub fn resolve_source(&self, owner: &str, repo: &str) -> Result<SourceResolution, String> {
+        let url = format!("{}/sources/github/{}/{}", self.base_url, owner, repo);
+        let res = self.send(self.client.get(&url))?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(SourceResolution::NotInstalled {
+                repo: format!("{}/{}", owner, repo),
+                install_url: JULES_INSTALL_URL.to_string(),
+            });
+        }
+        let source: SourceResource = Self::read(res, "Get Source")?;
+        Ok(SourceResolution::Connected { source: source.name })
+    }
+
+    /// Like resolve_source, but turns NotInstalled into an error for the engines.
+    pub fn require_source(&self, owner: &str, repo: &str) -> Result<String, String> {
+        match self.resolve_source(owner, repo)? {
+            SourceResolution::Connected { source } => Ok(source),
+            SourceResolution::NotInstalled { repo, install_url } => Err(format!(
+                "Jules is not installed on {}. Install it at {}", repo, install_url
+            )),
         }
+    }
+
+    pub fn poll_session(&self, session_id: &str) -> Result<(JobStatus, Option<PrDetails>), String> {
+        let session = self.get_session(session_id)?;
+        Ok((session.job_status(), session.pull_request))
+    }
 
-        let url = "https://jules.googleapis.com/v1/sessions"; // Hypothetical URL
-        let res = self.client.post(url)
-            .bearer_auth(&self.token)
-            .json(&json!({
-                "source": source,
-                "prompt": prompt,
-                "requirePlanApproval": require_approval
-            }))
-            .send()
-            .map_err(|e| e.to_string())?;
+    /// Approves the plan of a session created with `requirePlanApproval`.
+    pub fn resume_session(&self, session_id: &str) -> Result<(), String> {
+        let url = format!("{}/{}:approvePlan", self.base_url, session_id);
+        let res = self.send(self.client.post(&url).json(&Empty {}))?;
+        if !res.status().is_success() {
+             return Err(format!("Jules API Error (Approve Plan): {}", res.status()));
+        }
+        Ok(())
+    }
+
+    /// Stops a running session. Falls back to deleting it when cancel isn't supported.
+    pub fn cancel_session(&self, session_id: &str) -> Result<(), String> {
+        let url = format!("{}/{}:cancel", self.base_url, session_id);
+        let res = self.send(self.client.post(&url).json(&Empty {}))?;
+
+        match res.status() {
+            s if s.is_success() => Ok(()),
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+                self.delete_session(session_id)
+            }
+            s => Err(format!("Jules API Error (Cancel Session): {}", s)),
+        }
+    }
+
+    pub fn delete_session(&self, session_id: &str) -> Result<(), String> {
+        let url = format!("{}/{}", self.base_url, session_id);
+        let res = self.send(self.client.delete(&url))?;
+        if !res.status().is_success() {
+             return Err(format!("Jules API Error (Delete Session): {}", res.status()));
+        }
+        Ok(())
+    }
 
+    /// Sends a user message to the session. Works at any point before the session ends.
+    pub fn send_activity(&self, session_id: &str, message: &str) -> Result<(), String> {
+        let url = format!("{}/{}:sendMessage", self.base_url, session_id);
+        let res = self.send(self.client.post(&url).json(&SendMessageRequest { prompt: message }))?;
         if !res.status().is_success() {
-             return Err(format!("Jules API Error: {}", res.status()));
-        }
-
-        let body: serde_json::Value = res.json().map_err(|e| e.to_string())?;
-        Ok(body["name"].as_str().unwrap_or("").to_string())
-    }
-
-    pub fn poll_session(&self, session_id: &str) -> Result<(JobStatus, Option<PrDetails>, Option<String>), String> {
-         if self.token.starts_with("mock") {
-            // Randomly advance state for simulation
-             use rand::Rng;
-             let mut rng = rand::thread_rng();
-             let roll: f64 = rng.gen();
-
-             if roll < 0.3 {
-                 return Ok((JobStatus::Planning, None, None));
-             } else if roll < 0.6 {
-                 return Ok((JobStatus::Working, None, None));
-             } else if roll < 0.8 {
-                 return Ok((JobStatus::WaitingApproval, None, Some("Mock Plan: Create main.rs".to_string())));
-             } else {
-                 return Ok((JobStatus::PrReady, Some(PrDetails {
-                     number: 123,
-                     url: "https://github.com/mock/repo/pull/123".to_string(),
-                     title: "Mock PR".to_string()
-                 }), None));
-             }
-        }
-
-        let url = format!("https://jules.googleapis.com/v1/{}", session_id);
-        let res = self.client.get(&url)
-            .bearer_auth(&self.token)
-            .send()
-            .map_err(|e| e.to_string())?;
-
-        let body: serde_json::Value = res.json().map_err(|e| e.to_string())?;
-        let state = body["state"].as_str().unwrap_or("");
-
-        // Map Jules State to App JobStatus
-        if state == "WAITING_FOR_USER" {
-            let plan = body["inputs"]["plan_summary"].as_str().map(|s| s.to_string());
-            return Ok((JobStatus::WaitingApproval, None, plan));
-        } else if state == "SUCCEEDED" {
-            if let Some(outputs) = body["outputs"].as_array() {
-                for output in outputs {
-                    if let Some(pr) = output.get("pullRequest") {
-                        return Ok((JobStatus::PrReady, Some(PrDetails {
-                            number: 0, // Extract from URL or add to mock
-                            url: pr["url"].as_str().unwrap_or("").to_string(),
-                            title: pr["title"].as_str().unwrap_or("").to_string()
-                        }), None));
+             return Err(format!("Jules API Error (Send Message): {}", res.status()));
+        }
+        Ok(())
+    }
+
+    /// The session's activities after `after` (an activity id), reading from
+    /// `page_token` on, which is the page_token an earlier call returned.
+    /// Activities before the cursor is found are never returned, so a cursor
+    /// that can't be found yields nothing rather than the whole history again.
+    pub fn list_activities(&self, session_id: &str, after: Option<&str>, page_token: Option<&str>) -> Result<ActivityBatch, String> {
+        let path = format!("{}/activities", session_id);
+        let mut token = page_token.map(|t| t.to_string());
+        let mut found = after.is_none();
+        let mut activities = vec![];
+        let mut newest: Option<(String, Option<String>)> = None; // Last activity seen, and the token of its page
+
+        for _ in 0..MAX_ACTIVITY_PAGES {
+            let page: ListActivitiesResponse = self.get(&self.page_url(&path, token.as_deref())?, "List Activities")?;
+            let mut page_activities: Vec<Activity> = page.activities.into_iter().map(Activity::from).collect();
+            if let Some(last) = page_activities.last() {
+                newest = Some((last.id.clone(), token.clone()));
+            }
+            if !found {
+                match page_activities.iter().position(|a| Some(a.id.as_str()) == after) {
+                    Some(pos) => {
+                        page_activities.drain(..=pos);
+                        found = true;
                     }
+                    None => page_activities.clear(),
                 }
             }
-             return Ok((JobStatus::Merged, None, None)); // Or just done
-        } else if state == "RUNNING" {
-             return Ok((JobStatus::Working, None, None));
+            activities.extend(page_activities);
+
+            token = page.next_page_token.filter(|t| !t.is_empty());
+            if token.is_none() {
+                break;
+            }
+        }
+
+        // Out of pages before reaching the cursor: pick up from here next time
+        if !found && token.is_some() {
+            return Ok(ActivityBatch { activities, cursor: after.map(|a| a.to_string()), page_token: token });
+        }
+        // Otherwise continue after the newest activity. If the cursor's activity
+        // is gone, that skips ahead without replaying anything.
+        Ok(match newest {
+            Some((cursor, page_token)) => ActivityBatch { activities, cursor: Some(cursor), page_token },
+            None => ActivityBatch { activities, cursor: after.map(|a| a.to_string()), page_token: page_token.map(|t| t.to_string()) },
+        })
+    }
+}
+
+/// New activities, and where the next list_activities call should pick up.
+pub struct ActivityBatch {
+    pub activities: Vec<Activity>,
+    pub cursor: Option<String>,     // Id of the newest activity seen
+    pub page_token: Option<String>, // Page holding the cursor, None for the first page
+}
+
+const MAX_ACTIVITY_PAGES: usize = 20;
+const MAX_SESSION_PAGES: usize = 10;
+
+impl From<SourceResource> for Source {
+    fn from(s: SourceResource) -> Self {
+        Source { name: s.name, owner: s.github_repo.owner, repo: s.github_repo.repo }
+    }
+}
+
+impl From<SessionResource> for SessionSummary {
+    fn from(s: SessionResource) -> Self {
+        let pull_request = s.outputs.into_iter()
+            .find_map(|o| o.pull_request)
+            .map(|pr| PrDetails {
+                number: pr_number_from_url(&pr.url),
+                title: pr.title,
+                url: pr.url,
+            });
+
+        // "sources/github/owner/repo", or the older "github.com/owner/repo" form
+        let source = Some(s.source_context.source).filter(|s| !s.is_empty())
+            .or(s.source)
+            .unwrap_or_default();
+        let repo = source.strip_prefix("sources/github/")
+            .or_else(|| source.strip_prefix("github.com/"))
+            .filter(|r| r.contains('/'))
+            .map(|r| r.to_string());
+
+        SessionSummary {
+            name: s.name,
+            title: s.title,
+            state: s.state.unwrap_or(SessionState::StateUnspecified),
+            repo,
+            url: s.url,
+            pull_request,
         }
+    }
+}
+
+// ".../pull/123" -> 123
+fn pr_number_from_url(url: &str) -> u64 {
+    url.trim_end_matches('/').rsplit('/').next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
 
-        Ok((JobStatus::Planning, None, None))
+impl From<ActivityResource> for Activity {
+    fn from(a: ActivityResource) -> Self {
+        let kind = if let Some(p) = a.plan_generated {
+            ActivityKind::PlanGenerated { plan: to_plan(p.plan, &a.create_time) }
+        } else if let Some(p) = a.plan_approved {
+            ActivityKind::PlanApproved { plan_id: p.plan_id }
+        } else if let Some(p) = a.progress_updated {
+            ActivityKind::ProgressUpdated { title: p.title, description: p.description }
+        } else if let Some(m) = a.agent_messaged {
+            ActivityKind::AgentMessaged { message: m.agent_message }
+        } else if let Some(m) = a.user_messaged {
+            ActivityKind::UserMessaged { message: m.user_message }
+        } else if a.session_completed.is_some() {
+            ActivityKind::SessionCompleted
+        } else if let Some(f) = a.session_failed {
+            ActivityKind::SessionFailed { reason: f.reason }
+        } else {
+            ActivityKind::Other
+        };
+
+        let artifacts = a.artifacts.into_iter().map(|art| {
+            if let Some(bash) = art.bash_output {
+                Artifact::BashOutput { command: bash.command, output: bash.output, exit_code: bash.exit_code }
+            } else if let Some(change) = art.change_set {
+                Artifact::ChangeSet {
+                    files: change.git_patch.unidiff_patch.lines()
+                        .filter_map(|l| l.strip_prefix("+++ b/"))
+                        .map(|f| f.to_string())
+                        .collect(),
+                }
+            } else {
+                Artifact::Media
+            }
+        }).collect();
+
+        Activity {
+            id: a.id,
+            create_time: a.create_time,
+            originator: a.originator,
+            kind,
+            artifacts,
+        }
     }
+}
+
+fn to_plan(p: PlanResource, activity_time: &str) -> Plan {
+    let mut steps: Vec<PlanStep> = p.steps.into_iter().enumerate().map(|(i, step)| PlanStep {
+        index: step.index.unwrap_or(i as u32),
+        title: step.title,
+        description: step.description,
+    }).collect();
+    steps.sort_by_key(|step| step.index);
 
-    pub fn resume_session(&self, _session_id: &str) -> Result<(), String> {
-        // Call resume endpoint
-         if self.token.starts_with("mock") {
-             return Ok(());
-         }
-         // Implementation
-         Ok(())
+    Plan {
+        id: p.id,
+        steps,
+        create_time: p.create_time.unwrap_or_else(|| activity_time.to_string()),
     }
+}
 
-     pub fn send_activity(&self, _session_id: &str, _feedback: &str) -> Result<(), String> {
-        // Call resume endpoint
-         if self.token.starts_with("mock") {
-             return Ok(());
-         }
-         // Implementation
-         Ok(())
+/// Terminal log lines for an activity.
+pub fn describe_activity(activity: &Activity) -> Vec<String> {
+    let mut lines = vec![];
+    match &activity.kind {
+        ActivityKind::PlanGenerated { plan } => {
+            lines.push(format!("[plan] Plan generated with {} steps", plan.steps.len()));
+            lines.extend(plan.steps.iter().map(|s| format!("  {}. {}", s.index + 1, s.title)));
+        }
+        ActivityKind::PlanApproved { .. } => lines.push("[plan] Plan approved".to_string()),
+        ActivityKind::ProgressUpdated { title, description } if description.is_empty() => {
+            lines.push(format!("[progress] {}", title));
+        }
+        ActivityKind::ProgressUpdated { title, description } => {
+            lines.push(format!("[progress] {}: {}", title, description));
+        }
+        ActivityKind::AgentMessaged { message } => lines.push(format!("[jules] {}", message)),
+        ActivityKind::UserMessaged { message } => lines.push(format!("[you] {}", message)),
+        ActivityKind::SessionCompleted => lines.push("[done] Session completed".to_string()),
+        ActivityKind::SessionFailed { reason } => lines.push(format!("[failed] {}", reason)),
+        ActivityKind::Other => {}
+    }
+    for artifact in &activity.artifacts {
+        match artifact {
+            Artifact::BashOutput { command, exit_code, .. } => {
+                lines.push(format!("[bash] $ {} (exit {})", command, exit_code));
+            }
+            Artifact::ChangeSet { files } if !files.is_empty() => {
+                lines.push(format!("[files] {}", files.join(", ")));
+            }
+            Artifact::ChangeSet { .. } | Artifact::Media => {}
+        }
     }
+    lines
 }
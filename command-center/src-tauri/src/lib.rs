@@ -2,19 +2,88 @@
 
 mod types;
 mod auth;
+mod http_cache;
 mod github;
 mod jules;
 mod ssh_utils;
 mod scaffold_engine;
 mod uplink_engine;
+mod sweeper;
+mod messaging;
+mod mock_jules;
+mod poller;
+mod plans;
+mod scheduler;
+mod pipeline;
+mod steps;
+mod guards;
+mod job_store;
+mod resume;
+mod batch;
+mod prompts;
+mod schedules;
+mod automerge;
+mod dry_run;
 
-use types::{JobState, JobStatus, AgentMode, AuthState}; // PrDetails removed
+use types::{JobState, JobStatus, JobParams, AgentMode, AuthState, SessionSettings}; // PrDetails removed
 use std::sync::{Arc, Mutex};
-use tauri::State; // Manager removed
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Emitter, Manager, State};
 use std::collections::HashMap;
+use http_cache::ResponseCache;
+
+/// The tauri-plugin-store file every module keeps its settings in.
+const APP_STORE: &str = "app_store.json";
 
 struct AppState {
-    jobs: Arc<Mutex<HashMap<String, JobState>>>,
+    jobs: Arc<job_store::JobStore>,
+    github_cache: Arc<Mutex<ResponseCache>>,
+    cancellations: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    scheduler: Arc<scheduler::Scheduler>,
+    poll_nudges: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    schedules: Arc<schedules::Schedules>,
+}
+
+impl AppState {
+    /// Shared flag the engines check between steps. Set by cancel_job.
+    fn cancel_token(&self, job_id: &str) -> Arc<AtomicBool> {
+        self.cancellations.lock().unwrap()
+            .entry(job_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Set after a user action so the poll loop checks back sooner.
+    fn nudge_token(&self, job_id: &str) -> Arc<AtomicBool> {
+        self.poll_nudges.lock().unwrap()
+            .entry(job_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Only reaches a poll loop that is running; there is nothing to wake otherwise.
+    fn nudge(&self, job_id: &str) {
+        if let Some(nudge) = self.poll_nudges.lock().unwrap().get(job_id) {
+            nudge.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Stops the job's pipeline or poll loop, if one is running.
+    fn cancel(&self, job_id: &str) {
+        if let Some(cancel) = self.cancellations.lock().unwrap().get(job_id) {
+            cancel.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Drops the job's flags once nothing is running for it anymore.
+    fn forget(&self, job_id: &str) {
+        self.cancellations.lock().unwrap().remove(job_id);
+        self.poll_nudges.lock().unwrap().remove(job_id);
+    }
+
+    fn update_job(&self, job_id: &str, f: impl FnOnce(&mut JobState)) {
+        self.jobs.update(job_id, f);
+    }
 }
 
 #[tauri::command]
@@ -27,110 +96,423 @@ fn initiate_google_login() -> Result<(), String> {
     auth::initiate_google_login()
 }
 
+/// With `dry_run`, returns a report of what the job would do instead of starting it.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // One per field of the start form
 async fn start_scaffold_job(
     app: tauri::AppHandle,
-    state: State<'_, AppState>,
     name: String,
     recipe_id: String,
     context: String,
+    mode: AgentMode,
+    prompt: Option<String>,
+    session: Option<SessionSettings>,
+    dry_run: Option<bool>
+) -> Result<dry_run::JobStart, String> {
+    let session = session.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || match dry_run {
+        Some(true) => scaffold_engine::dry_run(&app, &name, &recipe_id, &context, mode, prompt.as_deref(), &session).map(dry_run::JobStart::DryRun),
+        _ => scaffold_engine::start_job(&app, name, recipe_id, context, mode, prompt, session).map(dry_run::JobStart::Started),
+    })
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// With `dry_run`, returns a report of what the job would do instead of starting it.
+#[tauri::command]
+async fn start_uplink_job(
+    app: tauri::AppHandle,
+    repo_url: String,
+    context: String,
+    mode: AgentMode,
+    prompt: Option<String>,
+    session: Option<SessionSettings>,
+    dry_run: Option<bool>
+) -> Result<dry_run::JobStart, String> {
+    let session = session.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || match dry_run {
+        Some(true) => uplink_engine::dry_run(&app, &repo_url, &context, mode, prompt.as_deref(), &session).map(dry_run::JobStart::DryRun),
+        _ => uplink_engine::start_job(&app, repo_url, context, mode, prompt, session).map(dry_run::JobStart::Started),
+    })
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// One uplink job per repo in `target`. Returns the batch; follow it with
+/// BATCH_UPDATE events or get_batch_progress.
+#[tauri::command]
+async fn start_batch_uplink(
+    app: tauri::AppHandle,
+    target: batch::BatchTarget,
+    context: String,
+    prompt: String,
     mode: AgentMode
-) -> Result<String, String> {
-    let job_id = uuid::Uuid::new_v4().to_string();
+) -> Result<batch::Batch, String> {
+    tauri::async_runtime::spawn_blocking(move || batch::start(&app, target, context, prompt, mode))
+        .await
+        .map_err(|e| e.to_string())?
+}
 
-    // Retrieve tokens
-    let gh_token = auth::get_github_token(&app).ok_or("GitHub not authenticated")?;
-    let google_token = auth::get_google_token(&app).ok_or("Google not authenticated")?;
-
-    // Store Job Initial State
-    {
-        let mut jobs = state.jobs.lock().unwrap();
-        jobs.insert(job_id.clone(), JobState {
-            id: job_id.clone(),
-            github_repo: format!("user/{}", name), // approximation
-            jules_session_id: None,
-            status: JobStatus::Booting,
-            last_poll: None,
-        });
-    }
+#[tauri::command]
+fn get_batch_progress(app: tauri::AppHandle, batch_id: String) -> Result<batch::BatchProgress, String> {
+    let batch = batch::get(&app, &batch_id).ok_or("Batch not found")?;
+    Ok(batch::progress(&app, &batch))
+}
+
+#[tauri::command]
+fn list_batches(app: tauri::AppHandle) -> Vec<batch::Batch> {
+    batch::list(&app)
+}
+
+#[tauri::command]
+fn list_schedules(state: State<'_, AppState>) -> Vec<schedules::Schedule> {
+    state.schedules.list()
+}
+
+/// `cron` is evaluated in local time, e.g. "0 9 * * MON" for Mondays at 9:00.
+#[tauri::command]
+fn create_schedule(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    cron: String,
+    task: schedules::ScheduledTask,
+    missed_runs: Option<schedules::MissedRuns>
+) -> Result<schedules::Schedule, String> {
+    state.schedules.create(&app, name, cron, task, missed_runs.unwrap_or_default())
+}
+
+#[tauri::command]
+fn set_schedule_paused(app: tauri::AppHandle, state: State<'_, AppState>, schedule_id: String, paused: bool) -> Result<schedules::Schedule, String> {
+    state.schedules.set_paused(&app, &schedule_id, paused)
+}
+
+#[tauri::command]
+fn delete_schedule(app: tauri::AppHandle, state: State<'_, AppState>, schedule_id: String) -> Result<(), String> {
+    state.schedules.delete(&app, &schedule_id)
+}
 
-    // Spawn Background Task via spawn_blocking since engines are synchronous/heavy
-    let job_id_clone = job_id.clone();
-    let app_handle = app.clone();
+#[tauri::command]
+fn list_prompt_templates(app: tauri::AppHandle) -> Vec<prompts::PromptTemplate> {
+    prompts::list(&app)
+}
+
+#[tauri::command]
+fn save_prompt_template(app: tauri::AppHandle, template: prompts::PromptTemplate) -> Result<prompts::PromptTemplate, String> {
+    prompts::save(&app, template)
+}
+
+#[tauri::command]
+fn delete_prompt_template(app: tauri::AppHandle, template_id: String) -> Result<(), String> {
+    prompts::delete(&app, &template_id)
+}
+
+#[tauri::command]
+async fn approve_agent_plan(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    job_id: String
+) -> Result<(), String> {
+    let session_id = {
+        let job = state.jobs.get(&job_id).ok_or("Job not found")?;
+        match job.status {
+            JobStatus::WaitingApproval => {}
+            JobStatus::WaitingFeedback => {
+                return Err(format!("Job {} is waiting for a reply, not a plan approval", job_id));
+            }
+            _ => return Err(format!("Job {} is not waiting for approval", job_id)),
+        }
+        job.jules_session_id.clone().ok_or("Job has no Jules session")?
+    };
+    let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
 
     tauri::async_runtime::spawn_blocking(move || {
-        let res = scaffold_engine::run_scaffold_job(
-            job_id_clone.clone(), name, recipe_id, context, mode, gh_token, google_token, app_handle.clone()
-        );
+        jules::JulesClient::new(jules_config).resume_session(&session_id)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // The poll loop clears this once Jules reports a status past WaitingApproval.
+    state.update_job(&job_id, |job| job.approval_pending = true);
+    state.nudge(&job_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn refine_agent_plan(app: tauri::AppHandle, job_id: String, feedback: String) -> Result<(), String> {
+    messaging::send_message(app, job_id, feedback).await
+}
+
+#[tauri::command]
+async fn send_agent_message(app: tauri::AppHandle, job_id: String, message: String) -> Result<(), String> {
+    messaging::send_message(app, job_id, message).await
+}
 
-        if let Err(e) = res {
-             println!("Job {} failed: {}", job_id_clone, e);
-             // In a real app, emit a failure event here
+#[tauri::command]
+fn get_plan_revisions(state: State<'_, AppState>, job_id: String) -> Result<Vec<plans::PlanRevision>, String> {
+    let job = state.jobs.get(&job_id).ok_or("Job not found")?;
+    Ok(plans::revisions(&job.plans))
+}
+
+#[tauri::command]
+async fn cancel_job(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    job_id: String
+) -> Result<(), String> {
+    // Nothing runs for an interrupted job, so the rollback path cleans up after it
+    let job = state.jobs.get(&job_id).ok_or("Job not found")?;
+    if job.status == JobStatus::Interrupted {
+        return tauri::async_runtime::spawn_blocking(move || resume::rollback_job(&app, &job_id))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let session_id = state.jobs.update(&job_id, |job| {
+        if job.status.is_terminal() {
+            return Err(format!("Job {} has already finished", job_id));
         }
-    });
+        // Jules is done with a PrReady session; there is nothing left to stop
+        let active = !job.status.ends_session();
+        job.status = JobStatus::Cancelled;
+        Ok(job.jules_session_id.clone().filter(|_| active))
+    }).ok_or("Job not found")??;
+    // The engine sees this at its next checkpoint and rolls back provisioning
+    state.cancel(&job_id);
 
-    Ok(job_id)
+    let mut logs = vec!["Job cancelled.".to_string()];
+    if let Some(session_id) = session_id {
+        let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+        let res = tauri::async_runtime::spawn_blocking(move || {
+            jules::JulesClient::new(jules_config).cancel_session(&session_id)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        match res {
+            Ok(()) => logs.push("Jules session cancelled.".to_string()),
+            Err(e) => logs.push(format!("Failed to cancel Jules session: {}", e)),
+        }
+    }
+
+    state.jobs.append_logs(&job_id, &logs);
+    if let Err(e) = app.emit("JOB_UPDATE", types::JobUpdateEvent::new(job_id, JobStatus::Cancelled, logs)) {
+        println!("Failed to emit event: {}", e);
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn start_uplink_job(
+fn list_jobs(state: State<'_, AppState>, query: Option<job_store::JobQuery>) -> job_store::JobPage {
+    state.jobs.query(&query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn get_job(state: State<'_, AppState>, job_id: String) -> Result<JobState, String> {
+    state.jobs.get(&job_id).ok_or_else(|| "Job not found".to_string())
+}
+
+/// `tail` limits the result to the last n lines.
+#[tauri::command]
+fn get_job_logs(state: State<'_, AppState>, job_id: String, tail: Option<usize>) -> Result<Vec<String>, String> {
+    let logs = state.jobs.logs(&job_id).ok_or("Job not found")?;
+    let skip = tail.map_or(0, |n| logs.len().saturating_sub(n));
+    Ok(logs.into_iter().skip(skip).collect())
+}
+
+#[tauri::command]
+async fn resume_job(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || resume::resume_job(&app, &job_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn rollback_job(app: tauri::AppHandle, job_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || resume::rollback_job(&app, &job_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn merge_pull_request(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
-    repo_url: String,
-    context: String,
-    mode: AgentMode
+    job_id: String
 ) -> Result<String, String> {
-    let job_id = uuid::Uuid::new_v4().to_string();
     let gh_token = auth::get_github_token(&app).ok_or("GitHub not authenticated")?;
-    let google_token = auth::get_google_token(&app).ok_or("Google not authenticated")?;
-
-    {
-        let mut jobs = state.jobs.lock().unwrap();
-        jobs.insert(job_id.clone(), JobState {
-            id: job_id.clone(),
-            github_repo: repo_url.clone(),
-            jules_session_id: None,
-            status: JobStatus::UploadingContext,
-            last_poll: None,
-        });
+    let (repo, pr) = {
+        let job = state.jobs.get(&job_id).ok_or("Job not found")?;
+        if job.status != JobStatus::PrReady {
+            return Err(format!("Job {} has no pull request ready to merge", job_id));
+        }
+        let pr = job.pr_details.clone().ok_or("Job has no pull request")?;
+        (job.github_repo.clone(), pr)
+    };
+    // Older jobs stored the repo URL rather than "owner/repo"
+    let (owner, name) = uplink_engine::parse_repo_url(&repo)?;
+
+    let gh = github::GithubClient::new(gh_token).with_cache(state.github_cache.clone());
+    tauri::async_runtime::spawn_blocking(move || gh.merge_pull_request(&owner, &name, pr.number, types::MergeMethod::default(), None))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    poller::emit_update(&app, types::JobUpdateEvent::new(job_id, JobStatus::Merged, vec![format!("Merged PR #{}.", pr.number)]));
+    Ok("Merged".to_string())
+}
+
+/// Sets or clears (None) the job's own auto-merge policy, which wins over its
+/// repo's. A job whose PR is already ready is evaluated right away.
+#[tauri::command]
+fn set_job_merge_policy(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    job_id: String,
+    policy: Option<types::MergePolicy>
+) -> Result<(), String> {
+    let enabled = policy.is_some();
+    let status = state.jobs.update(&job_id, |job| {
+        job.merge_policy = policy;
+        job.status.clone()
+    }).ok_or("Job not found")?;
+    if enabled && status == JobStatus::PrReady {
+        let gh_token = auth::get_github_token(&app).ok_or("GitHub not authenticated")?;
+        let gh = github::GithubClient::new(gh_token).with_cache(state.github_cache.clone());
+        automerge::spawn(&app, gh, &job_id);
     }
+    Ok(())
+}
 
-    let job_id_clone = job_id.clone();
-    let app_handle = app.clone();
+/// Auto-merge policies keyed by owner/repo.
+#[tauri::command]
+fn get_repo_merge_policies(app: tauri::AppHandle) -> HashMap<String, types::MergePolicy> {
+    automerge::repo_policies(&app)
+}
 
-    tauri::async_runtime::spawn_blocking(move || {
-         let res = uplink_engine::run_uplink_job(
-            job_id_clone.clone(), repo_url, context, mode, gh_token, google_token, app_handle
-        );
-         if let Err(e) = res {
-             println!("Job {} failed: {}", job_id_clone, e);
-        }
+#[tauri::command]
+fn set_repo_merge_policy(app: tauri::AppHandle, repo: String, policy: Option<types::MergePolicy>) -> Result<(), String> {
+    let enabled = policy.is_some();
+    automerge::set_repo_policy(&app, &repo, policy)?;
+    if enabled {
+        automerge::repo_policy_changed(&app, &repo);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_jules_sessions(app: tauri::AppHandle) -> Result<Vec<jules::SessionSummary>, String> {
+    let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+    tauri::async_runtime::spawn_blocking(move || jules::JulesClient::new(jules_config).list_sessions())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Points the app at a different Jules endpoint and/or switches to API-key auth.
+#[tauri::command]
+fn configure_jules(app: tauri::AppHandle, base_url: Option<String>, api_key: Option<String>) -> Result<(), String> {
+    auth::set_jules_config(&app, base_url, api_key)
+}
+
+/// Onboarding check for the wizard: is the Jules GitHub app installed on this repo?
+#[tauri::command]
+async fn check_jules_source(app: tauri::AppHandle, repo_url: String) -> Result<jules::SourceResolution, String> {
+    let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+    let (owner, repo) = uplink_engine::parse_repo_url(&repo_url)?;
+    tauri::async_runtime::spawn_blocking(move || jules::JulesClient::new(jules_config).resolve_source(&owner, &repo))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn list_jules_sources(app: tauri::AppHandle) -> Result<Vec<jules::Source>, String> {
+    let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+    tauri::async_runtime::spawn_blocking(move || jules::JulesClient::new(jules_config).list_sources())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Tracks a session started outside the app (e.g. the Jules web UI) as a regular job.
+#[tauri::command]
+async fn import_session(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    session_id: String
+) -> Result<String, String> {
+    if let Some(job) = state.jobs.find(|j| j.jules_session_id.as_deref() == Some(session_id.as_str())) {
+        return Err(format!("Session is already tracked by job {}", job.id));
+    }
+    let jules_config = auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+
+    let config = jules_config.clone();
+    let id = session_id.clone();
+    let session = tauri::async_runtime::spawn_blocking(move || jules::JulesClient::new(config).get_session(&id))
+        .await
+        .map_err(|e| e.to_string())??;
+    let repo = session.repo.clone().ok_or("Session is not linked to a GitHub repo")?;
+    let status = session.job_status();
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let mut job = JobState::new(job_id.clone(), repo.clone(), status.clone(),
+        JobParams::Import { session_id: session_id.clone() });
+    job.jules_session_id = Some(session.name.clone());
+    job.pr_details = session.pull_request.clone();
+    state.jobs.insert(job);
+
+    poller::emit_update(&app, types::JobUpdateEvent {
+        pr_details: session.pull_request,
+        ..types::JobUpdateEvent::new(&job_id, status.clone(), vec![format!("Imported Jules session \"{}\" on {}", session.title, repo)])
     });
 
+    if !status.ends_session() {
+        resume::follow_session(&app, &job_id, session.name, jules_config);
+    }
+
     Ok(job_id)
 }
 
 #[tauri::command]
-async fn approve_agent_plan(_job_id: String) -> Result<(), String> {
-    // Logic to resume session
-    // In a real implementation, this would look up the session ID from the job store and call Jules API
-    Ok(())
+fn get_poll_policy(app: tauri::AppHandle) -> poller::PollPolicy {
+    poller::PollPolicy::load(&app)
 }
 
+/// Applies to polls started after the change.
 #[tauri::command]
-async fn refine_agent_plan(_job_id: String, _feedback: String) -> Result<(), String> {
-    // Logic to send feedback
-    Ok(())
+fn set_poll_policy(app: tauri::AppHandle, policy: poller::PollPolicy) -> Result<(), String> {
+    policy.save(&app)
+}
+
+#[tauri::command]
+fn get_jules_quota(state: State<'_, AppState>) -> scheduler::QuotaUsage {
+    state.scheduler.usage()
+}
+
+#[tauri::command]
+fn set_jules_limits(app: tauri::AppHandle, state: State<'_, AppState>, limits: scheduler::QuotaLimits) -> Result<(), String> {
+    state.scheduler.set_limits(&app, limits)
 }
 
 #[tauri::command]
-async fn merge_pull_request(app: tauri::AppHandle, _job_id: String) -> Result<String, String> {
+async fn scan_orphaned_resources(app: tauri::AppHandle) -> Result<Vec<sweeper::OrphanResource>, String> {
     let gh_token = auth::get_github_token(&app).ok_or("GitHub not authenticated")?;
-    // Mock logic: get repo info from job state
-    let gh = github::GithubClient::new(gh_token);
-    // Hardcoded for prototype
-    gh.merge_pull_request("owner", "repo", 123)?;
-    Ok("Merged".to_string())
+    let gh = github::GithubClient::new(gh_token)
+        .with_cache(app.state::<AppState>().github_cache.clone());
+
+    tauri::async_runtime::spawn_blocking(move || sweeper::scan_for_app(&app, &gh))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn delete_orphaned_resources(
+    app: tauri::AppHandle,
+    resources: Vec<sweeper::OrphanResource>
+) -> Result<Vec<sweeper::SweepResult>, String> {
+    let gh_token = auth::get_github_token(&app).ok_or("GitHub not authenticated")?;
+    let gh = github::GithubClient::new(gh_token)
+        .with_cache(app.state::<AppState>().github_cache.clone());
+
+    tauri::async_runtime::spawn_blocking(move || sweeper::sweep_confirmed(&app, &gh, resources))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -138,18 +520,99 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_opener::init())
-        .manage(AppState {
-            jobs: Arc::new(Mutex::new(HashMap::new())),
+        .setup(|app| {
+            let mut github_cache = ResponseCache::new(http_cache::DEFAULT_MAX_ENTRIES);
+            let mut jobs = job_store::JobStore::memory();
+            match app.path().app_data_dir() {
+                Ok(dir) => {
+                    github_cache.attach(dir.join("github_cache.json"));
+                    match std::fs::create_dir_all(&dir) {
+                        Ok(()) => jobs = job_store::JobStore::open(dir.join(job_store::JOBS_FILE)),
+                        Err(e) => println!("Failed to create app data dir, jobs won't be saved: {}", e),
+                    }
+                }
+                Err(e) => println!("No app data dir, HTTP cache and jobs are memory only: {}", e),
+            }
+
+            let github_cache = Arc::new(Mutex::new(github_cache));
+            http_cache::start_flusher(github_cache.clone());
+            app.manage(AppState {
+                jobs: Arc::new(jobs),
+                github_cache,
+                cancellations: Arc::new(Mutex::new(HashMap::new())),
+                scheduler: Arc::new(scheduler::Scheduler::load(app.handle())),
+                poll_nudges: Arc::new(Mutex::new(HashMap::new())),
+                schedules: Arc::new(schedules::Schedules::load(app.handle())),
+            });
+            // Before the ticker, so a schedule never sees a job from the last run
+            // that hasn't been reattached or marked Interrupted yet. Also before the
+            // orphan scan, so it sees interrupted jobs as such.
+            resume::resume_all(app.handle());
+            schedules::start_ticker(app.handle().clone());
+
+            // Look for leftovers from previous runs. Nothing is deleted until the
+            // user confirms via delete_orphaned_resources.
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let Some(gh_token) = auth::get_github_token(&handle) else { return };
+                let gh = github::GithubClient::new(gh_token)
+                    .with_cache(handle.state::<AppState>().github_cache.clone());
+                match sweeper::scan_for_app(&handle, &gh) {
+                    Ok(orphans) if !orphans.is_empty() => {
+                        let _ = handle.emit("ORPHANS_FOUND", orphans);
+                    }
+                    Ok(_) => {}
+                    Err(e) => println!("Orphan scan failed: {}", e),
+                }
+            });
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             check_auth_status,
             initiate_google_login,
             start_scaffold_job,
             start_uplink_job,
+            start_batch_uplink,
+            get_batch_progress,
+            list_batches,
+            list_schedules,
+            create_schedule,
+            set_schedule_paused,
+            delete_schedule,
+            list_prompt_templates,
+            save_prompt_template,
+            delete_prompt_template,
             approve_agent_plan,
             refine_agent_plan,
-            merge_pull_request
+            send_agent_message,
+            get_plan_revisions,
+            cancel_job,
+            list_jobs,
+            get_job,
+            get_job_logs,
+            resume_job,
+            rollback_job,
+            merge_pull_request,
+            set_job_merge_policy,
+            get_repo_merge_policies,
+            set_repo_merge_policy,
+            list_jules_sessions,
+            import_session,
+            check_jules_source,
+            configure_jules,
+            list_jules_sources,
+            get_jules_quota,
+            get_poll_policy,
+            set_poll_policy,
+            set_jules_limits,
+            scan_orphaned_resources,
+            delete_orphaned_resources
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                http_cache::flush(&app.state::<AppState>().github_cache);
+            }
+        });
 }
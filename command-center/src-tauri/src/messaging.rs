@@ -0,0 +1,68 @@
+use crate::jules::JulesClient;
+use crate::types::{Activity, ActivityKind, JobMessage, JobUpdateEvent};
+use tauri::{Emitter, Manager};
+
+pub async fn send_message(app: tauri::AppHandle, job_id: String, text: String) -> Result<(), String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+
+    let (session_id, status) = {
+        let state = app.state::<crate::AppState>();
+        let job = state.jobs.get(&job_id).ok_or("Job not found")?;
+        if job.status.is_terminal() {
+            return Err(format!("Job {} has already finished", job_id));
+        }
+        let session_id = job.jules_session_id.clone().ok_or("Job has no Jules session")?;
+        (session_id, job.status.clone())
+    };
+    let jules_config = crate::auth::get_jules_config(&app).ok_or("Jules not authenticated")?;
+
+    let message = text.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        JulesClient::new(jules_config).send_activity(&session_id, &message)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let state = app.state::<crate::AppState>();
+    state.update_job(&job_id, |job| {
+        job.messages.push(JobMessage {
+            text: text.clone(),
+            sent_at: chrono::Utc::now().to_rfc3339(),
+            acknowledged: false,
+        });
+    });
+    // The reply usually follows quickly
+    state.nudge(&job_id);
+
+    let logs = vec![format!(">> You: {}", text)];
+    state.jobs.append_logs(&job_id, &logs);
+    if let Err(e) = app.emit("JOB_UPDATE", JobUpdateEvent::new(job_id, status, logs)) {
+        println!("Failed to emit event: {}", e);
+    }
+    Ok(())
+}
+
+/// Marks messages the agent has replied to, based on activities from the latest poll.
+/// Returns one log line per newly acknowledged message.
+pub fn check_acknowledgements(app: &tauri::AppHandle, job_id: &str, activities: &[Activity]) -> Vec<String> {
+    let replies: Vec<_> = activities.iter()
+        .filter(|a| matches!(a.kind, ActivityKind::AgentMessaged { .. }))
+        .filter_map(|a| chrono::DateTime::parse_from_rfc3339(&a.create_time).ok())
+        .collect();
+    let Some(latest_reply) = replies.into_iter().max() else { return vec![] };
+
+    let mut logs = vec![];
+    app.state::<crate::AppState>().update_job(job_id, |job| {
+        for m in job.messages.iter_mut().filter(|m| !m.acknowledged) {
+            let sent_at = chrono::DateTime::parse_from_rfc3339(&m.sent_at);
+            if sent_at.is_ok_and(|t| t < latest_reply) {
+                m.acknowledged = true;
+                logs.push(format!("Jules acknowledged: \"{}\"", m.text));
+            }
+        }
+    });
+    logs
+}
@@ -0,0 +1,688 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+// Local stand-in for the Jules API. Sessions follow a fixed script that advances
+// one step per GET of the session, so the same scenario always plays out the
+// same way. JulesClient uses it whenever it is given a mock token.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scenario {
+    HappyPath,    // plan -> approval -> working -> PR
+    Failure,      // fails while working
+    FeedbackLoop, // asks a question and waits for a reply before planning
+    Slow,         // happy path with every step held for several polls
+    Chatty,       // happy path with 250 progress updates, several pages of activities
+}
+
+impl Scenario {
+    fn parse(s: &str) -> Option<Scenario> {
+        match s {
+            "happy_path" => Some(Scenario::HappyPath),
+            "failure" => Some(Scenario::Failure),
+            "feedback_loop" => Some(Scenario::FeedbackLoop),
+            "slow" => Some(Scenario::Slow),
+            "chatty" => Some(Scenario::Chatty),
+            _ => None,
+        }
+    }
+
+    // A prompt containing "[mock:failure]" picks that scenario for the session
+    fn from_prompt(prompt: &str) -> Option<Scenario> {
+        let start = prompt.find("[mock:")? + "[mock:".len();
+        let end = start + prompt[start..].find(']')?;
+        Scenario::parse(&prompt[start..end])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Gate {
+    None,
+    Approval,
+    Message,
+}
+
+struct ScriptStep {
+    state: &'static str,
+    polls: u32, // Polls spent in this state before moving on
+    gate: Gate,
+    activities: Vec<Value>, // Emitted on entering the step
+}
+
+fn step(state: &'static str, polls: u32, gate: Gate, activities: Vec<Value>) -> ScriptStep {
+    ScriptStep { state, polls, gate, activities }
+}
+
+fn script(scenario: Scenario, require_approval: bool) -> Vec<ScriptStep> {
+    let plan_steps = |approval: bool| -> ScriptStep {
+        if approval {
+            step("AWAITING_PLAN_APPROVAL", 1, Gate::Approval, vec![plan_generated()])
+        } else {
+            step("PLANNING", 1, Gate::None, vec![plan_generated(), json!({ "planApproved": { "planId": "mock-plan-1" } })])
+        }
+    };
+
+    let mut steps = vec![
+        step("QUEUED", 1, Gate::None, vec![]),
+        step("PLANNING", 1, Gate::None, vec![progress("Reading repository", "Indexing files and AGENTS.md")]),
+    ];
+
+    match scenario {
+        Scenario::HappyPath | Scenario::Slow => {
+            steps.push(plan_steps(require_approval));
+            steps.push(working());
+            steps.push(completed());
+        }
+        Scenario::Failure => {
+            steps.push(plan_steps(require_approval));
+            steps.push(step("IN_PROGRESS", 1, Gate::None, vec![
+                progress("Running tests", ""),
+                bash("cargo test", "test result: FAILED. 3 passed; 1 failed", 101),
+            ]));
+            steps.push(step("FAILED", 0, Gate::None, vec![
+                json!({ "sessionFailed": { "reason": "Mock failure: tests did not pass" } }),
+            ]));
+        }
+        Scenario::Chatty => {
+            steps.push(plan_steps(require_approval));
+            let updates = (1..=250).map(|i| progress(&format!("Step {}", i), "")).collect();
+            steps.push(step("IN_PROGRESS", 1, Gate::None, updates));
+            steps.push(completed());
+        }
+        Scenario::FeedbackLoop => {
+            steps.push(step("AWAITING_USER_FEEDBACK", 1, Gate::Message, vec![
+                json!({ "agentMessaged": { "agentMessage": "Should the new module be public?" } }),
+            ]));
+            steps.push(plan_steps(require_approval));
+            steps.push(working());
+            steps.push(completed());
+        }
+    }
+
+    if scenario == Scenario::Slow {
+        for s in &mut steps {
+            s.polls *= 6;
+        }
+    }
+    steps
+}
+
+fn plan_generated() -> Value {
+    revised_plan(1, None)
+}
+
+// Feedback during plan review reworks the test step and appends a follow-up step
+fn revised_plan(revision: u32, feedback: Option<&str>) -> Value {
+    let mut steps = vec![
+        json!({ "id": "1", "index": 0, "title": "Create main.rs", "description": "Add the entry point" }),
+        json!({ "id": "2", "index": 1, "title": "Add tests", "description": "Cover the new module" }),
+    ];
+    if let Some(feedback) = feedback {
+        steps[1]["description"] = json!(format!("Cover the new module, taking into account: {}", feedback));
+        steps.push(json!({ "id": "3", "index": 2, "title": "Address review feedback", "description": feedback }));
+    }
+    json!({
+        "planGenerated": {
+            "plan": { "id": format!("mock-plan-{}", revision), "steps": steps }
+        }
+    })
+}
+
+fn progress(title: &str, description: &str) -> Value {
+    json!({ "progressUpdated": { "title": title, "description": description } })
+}
+
+fn bash(command: &str, output: &str, exit_code: i64) -> Value {
+    json!({
+        "progressUpdated": { "title": format!("Ran {}", command), "description": "" },
+        "artifacts": [{ "bashOutput": { "command": command, "output": output, "exitCode": exit_code } }]
+    })
+}
+
+fn working() -> ScriptStep {
+    step("IN_PROGRESS", 2, Gate::None, vec![
+        progress("Implementing plan", "Writing code"),
+        bash("cargo build", "Finished dev profile", 0),
+        json!({
+            "progressUpdated": { "title": "Changed files", "description": "" },
+            "artifacts": [{ "changeSet": { "gitPatch": {
+                "unidiffPatch": "--- a/src/main.rs\n+++ b/src/main.rs\n@@\n+fn main() {}\n"
+            } } }]
+        }),
+    ])
+}
+
+fn completed() -> ScriptStep {
+    step("COMPLETED", 0, Gate::None, vec![json!({ "sessionCompleted": {} })])
+}
+
+struct MockSession {
+    name: String,
+    title: String,
+    source: String,
+    steps: Vec<ScriptStep>,
+    current: usize,
+    polls: u32,
+    approved: bool,
+    messaged: bool,
+    plan_revision: u32,
+    auto_create_pr: bool,
+    activities: Vec<Value>,
+    pending_replies: Vec<Value>, // Agent replies, released on the next poll
+}
+
+impl MockSession {
+    fn state(&self) -> &'static str {
+        self.steps[self.current].state
+    }
+
+    fn push_activity(&mut self, mut activity: Value, originator: &str) {
+        let id = format!("act-{}", self.activities.len() + 1);
+        activity["name"] = json!(format!("{}/activities/{}", self.name, id));
+        activity["id"] = json!(id);
+        activity["originator"] = json!(originator);
+        activity["createTime"] = json!(chrono::Utc::now().to_rfc3339());
+        self.activities.push(activity);
+    }
+
+    fn enter(&mut self, index: usize) {
+        self.current = index;
+        self.polls = 0;
+        for activity in self.steps[index].activities.clone() {
+            self.push_activity(activity, "agent");
+        }
+    }
+
+    fn advance(&mut self) {
+        for reply in std::mem::take(&mut self.pending_replies) {
+            self.push_activity(reply, "agent");
+        }
+
+        let step = &self.steps[self.current];
+        let gate_open = match step.gate {
+            Gate::None => true,
+            Gate::Approval => self.approved,
+            Gate::Message => self.messaged,
+        };
+        if self.polls >= step.polls && gate_open && self.current + 1 < self.steps.len() {
+            self.enter(self.current + 1);
+        } else {
+            self.polls += 1;
+        }
+    }
+
+    // The session fails right away; the rest of its script never plays
+    fn cancel(&mut self) {
+        self.steps.truncate(self.current + 1);
+        self.steps.push(step("FAILED", 0, Gate::None, vec![
+            json!({ "sessionFailed": { "reason": "Cancelled by the user" } }),
+        ]));
+        self.enter(self.current + 1);
+    }
+
+    // Up to `page_size` activities from `offset`, with a token for the rest.
+    // Tokens contain a '+', as base64 tokens do, so one sent without URL
+    // encoding reads back as a space and is rejected.
+    fn activities_page(&self, offset: usize, page_size: usize) -> Value {
+        let end = (offset + page_size).min(self.activities.len());
+        let mut body = json!({ "activities": self.activities[offset.min(end)..end] });
+        if end < self.activities.len() {
+            body["nextPageToken"] = json!(format!("o+{}", end));
+        }
+        body
+    }
+
+    fn to_json(&self) -> Value {
+        let mut body = json!({
+            "name": self.name,
+            "id": self.name.trim_start_matches("sessions/"),
+            "title": self.title,
+            "sourceContext": { "source": self.source },
+            "state": self.state(),
+        });
+        if self.state() == "COMPLETED" && self.auto_create_pr {
+            body["outputs"] = json!([{
+                "pullRequest": {
+                    "url": "https://github.com/mock/repo/pull/123",
+                    "title": "Mock PR",
+                    "description": "Changes from the mock Jules session"
+                }
+            }]);
+        }
+        body
+    }
+}
+
+struct ServerState {
+    default_scenario: Scenario,
+    sessions: HashMap<String, MockSession>,
+    next_id: u64,
+}
+
+pub struct MockJulesServer {
+    base_url: String,
+}
+
+impl MockJulesServer {
+    /// Binds to an ephemeral localhost port and serves requests on a background thread.
+    pub fn start(default_scenario: Scenario) -> Result<Self, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let state = Arc::new(Mutex::new(ServerState {
+            default_scenario,
+            sessions: HashMap::new(),
+            next_id: 1,
+        }));
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = state.clone();
+                thread::spawn(move || {
+                    if let Err(e) = handle(stream, &state) {
+                        println!("Mock Jules request failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(MockJulesServer { base_url: format!("http://{}", addr) })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+/// Shared server used when running with mock tokens. The scenario can be picked
+/// with COMMAND_CENTER_MOCK_SCENARIO (happy_path, failure, feedback_loop, slow, chatty).
+pub fn demo_base_url() -> String {
+    static DEMO: OnceLock<Result<MockJulesServer, String>> = OnceLock::new();
+    let server = DEMO.get_or_init(|| {
+        let scenario = std::env::var("COMMAND_CENTER_MOCK_SCENARIO").ok()
+            .and_then(|s| Scenario::parse(&s))
+            .unwrap_or(Scenario::HappyPath);
+        MockJulesServer::start(scenario)
+    });
+    match server {
+        Ok(server) => server.base_url().to_string(),
+        Err(e) => {
+            println!("Failed to start mock Jules server: {}", e);
+            "http://127.0.0.1:0".to_string()
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<ServerState>) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|e| e.to_string())?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let query = parse_query(query);
+    let (status, response) = route(&method, path, &query, &body, &mut state.lock().unwrap());
+
+    let payload = response.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, payload.len(), payload
+    ).map_err(|e| e.to_string())
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (percent_decode(name), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'+', _) => out.push(b' '),
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 2;
+            }
+            (byte, _) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn mock_source(owner: &str, repo: &str) -> Value {
+    json!({
+        "name": format!("sources/github/{}/{}", owner, repo),
+        "id": format!("github/{}/{}", owner, repo),
+        "githubRepo": { "owner": owner, "repo": repo }
+    })
+}
+
+fn error(status: &'static str, message: &str) -> (&'static str, Value) {
+    (status, json!({ "error": { "message": message } }))
+}
+
+fn route(method: &str, path: &str, query: &HashMap<String, String>, body: &Value, state: &mut ServerState) -> (&'static str, Value) {
+    let path = path.trim_start_matches('/');
+
+    if method == "POST" && path == "sessions" {
+        let prompt = body["prompt"].as_str().unwrap_or("");
+        let scenario = Scenario::from_prompt(prompt).unwrap_or(state.default_scenario);
+        let require_approval = body["requirePlanApproval"].as_bool().unwrap_or(false);
+
+        let name = format!("sessions/mock-{}", state.next_id);
+        state.next_id += 1;
+        let mut session = MockSession {
+            name: name.clone(),
+            title: body["title"].as_str().unwrap_or(prompt).to_string(),
+            source: body["sourceContext"]["source"].as_str().unwrap_or("").to_string(),
+            steps: script(scenario, require_approval),
+            current: 0,
+            polls: 0,
+            approved: false,
+            messaged: false,
+            plan_revision: 1,
+            auto_create_pr: body["automationMode"].as_str() == Some("AUTO_CREATE_PR"),
+            activities: vec![],
+            pending_replies: vec![],
+        };
+        session.enter(0);
+        let response = session.to_json();
+        state.sessions.insert(name, session);
+        return ("200 OK", response);
+    }
+
+    // Every repo counts as connected except ones named "unconnected-*"
+    if method == "GET" && path == "sources" {
+        return ("200 OK", json!({ "sources": [mock_source("mock", "repo")] }));
+    }
+    if method == "GET" {
+        if let Some((owner, repo)) = path.strip_prefix("sources/github/").and_then(|r| r.split_once('/')) {
+            if repo.starts_with("unconnected-") {
+                return error("404 Not Found", "Source not found");
+            }
+            return ("200 OK", mock_source(owner, repo));
+        }
+    }
+
+    if method == "GET" && path == "sessions" {
+        let mut sessions: Vec<&MockSession> = state.sessions.values().collect();
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        let sessions: Vec<Value> = sessions.iter().map(|s| s.to_json()).collect();
+        return ("200 OK", json!({ "sessions": sessions }));
+    }
+
+    // sessions/{id}, sessions/{id}:action, sessions/{id}/activities
+    let (resource, action) = match path.rsplit_once(':') {
+        Some((resource, action)) => (resource, Some(action)),
+        None => (path, None),
+    };
+    let (name, sub) = match resource.strip_suffix("/activities") {
+        Some(name) => (name, Some("activities")),
+        None => (resource, None),
+    };
+    if method == "DELETE" && sub.is_none() && action.is_none() {
+        return match state.sessions.remove(name) {
+            Some(_) => ("200 OK", json!({})),
+            None => error("404 Not Found", "Session not found"),
+        };
+    }
+    let Some(session) = state.sessions.get_mut(name) else {
+        return error("404 Not Found", "Session not found");
+    };
+
+    match (method, sub, action) {
+        ("GET", None, None) => {
+            session.advance();
+            ("200 OK", session.to_json())
+        }
+        ("GET", Some("activities"), None) => {
+            let page_size = query.get("pageSize").and_then(|s| s.parse().ok()).unwrap_or(100).clamp(1, 100);
+            let offset = match query.get("pageToken") {
+                None => 0,
+                Some(token) => match token.strip_prefix("o+").and_then(|n| n.parse().ok()) {
+                    Some(offset) => offset,
+                    None => return error("400 Bad Request", "Invalid page token"),
+                },
+            };
+            ("200 OK", session.activities_page(offset, page_size))
+        }
+        ("POST", None, Some("cancel")) => {
+            if matches!(session.state(), "COMPLETED" | "FAILED") {
+                return error("400 Bad Request", "Session has ended");
+            }
+            session.cancel();
+            ("200 OK", json!({}))
+        }
+        ("POST", None, Some("approvePlan")) => {
+            if session.state() != "AWAITING_PLAN_APPROVAL" {
+                return error("400 Bad Request", "Session is not awaiting plan approval");
+            }
+            session.approved = true;
+            let plan_id = format!("mock-plan-{}", session.plan_revision);
+            session.push_activity(json!({ "planApproved": { "planId": plan_id } }), "user");
+            ("200 OK", json!({}))
+        }
+        ("POST", None, Some("sendMessage")) => {
+            if matches!(session.state(), "COMPLETED" | "FAILED") {
+                return error("400 Bad Request", "Session has ended");
+            }
+            let prompt = body["prompt"].as_str().unwrap_or("").to_string();
+            session.messaged = true;
+            session.push_activity(json!({ "userMessaged": { "userMessage": prompt } }), "user");
+            session.pending_replies.push(json!({
+                "agentMessaged": { "agentMessage": format!("Got it: {}", prompt) }
+            }));
+            if session.state() == "AWAITING_PLAN_APPROVAL" {
+                session.plan_revision += 1;
+                session.pending_replies.push(revised_plan(session.plan_revision, Some(&prompt)));
+            }
+            ("200 OK", json!({}))
+        }
+        _ => error("404 Not Found", "Unknown endpoint"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jules::{JulesAuth, JulesClient, JulesConfig, SessionOptions};
+    use crate::types::{ActivityKind, PrDetails};
+
+    const SOURCE: &str = "sources/github/mock/repo";
+
+    fn client(server: &MockJulesServer) -> JulesClient {
+        JulesClient::new(JulesConfig {
+            base_url: Some(server.base_url().to_string()),
+            auth: JulesAuth::ApiKey("test-key".to_string()),
+        })
+    }
+
+    fn start(jules: &JulesClient, prompt: &str, require_plan_approval: bool) -> String {
+        let options = SessionOptions { require_plan_approval, ..SessionOptions::default() };
+        jules.start_session(SOURCE, prompt, &options).unwrap()
+    }
+
+    // Polls until the session's status has the key `want`, failing if it ends otherwise
+    fn poll_until(jules: &JulesClient, session: &str, want: &str) -> Option<PrDetails> {
+        for _ in 0..100 {
+            let (status, pr) = jules.poll_session(session).unwrap();
+            if status.key() == want {
+                return pr;
+            }
+            assert!(!status.ends_session(), "session ended as {:?} while waiting for {}", status, want);
+        }
+        panic!("session never reached {}", want);
+    }
+
+    #[test]
+    fn plan_approval_leads_to_a_pull_request() {
+        let server = MockJulesServer::start(Scenario::HappyPath).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Add a main function", true);
+
+        poll_until(&jules, &session, "waiting_approval");
+        let batch = jules.list_activities(&session, None, None).unwrap();
+        let plan = batch.activities.iter().find_map(|a| match &a.kind {
+            ActivityKind::PlanGenerated { plan } => Some(plan.clone()),
+            _ => None,
+        });
+        assert_eq!(plan.map(|p| p.steps.len()), Some(2));
+
+        jules.resume_session(&session).unwrap();
+        let pr = poll_until(&jules, &session, "pr_ready").unwrap();
+        assert_eq!(pr.number, 123);
+
+        let batch = jules.list_activities(&session, batch.cursor.as_deref(), batch.page_token.as_deref()).unwrap();
+        assert!(matches!(batch.activities.first().map(|a| &a.kind), Some(ActivityKind::PlanApproved { .. })));
+        assert!(matches!(batch.activities.last().map(|a| &a.kind), Some(ActivityKind::SessionCompleted)));
+    }
+
+    #[test]
+    fn a_question_is_answered_with_a_message_not_an_approval() {
+        let server = MockJulesServer::start(Scenario::FeedbackLoop).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Add a module", false);
+
+        poll_until(&jules, &session, "waiting_feedback");
+        assert!(jules.resume_session(&session).is_err());
+        jules.send_activity(&session, "Yes, make it public").unwrap();
+        poll_until(&jules, &session, "pr_ready");
+    }
+
+    #[test]
+    fn a_failed_session_reports_its_reason_in_the_activities() {
+        let server = MockJulesServer::start(Scenario::HappyPath).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Fix the tests [mock:failure]", false);
+
+        let mut status = jules.poll_session(&session).unwrap().0;
+        for _ in 0..20 {
+            if status.ends_session() {
+                break;
+            }
+            status = jules.poll_session(&session).unwrap().0;
+        }
+        assert_eq!(status.key(), "failed");
+
+        let batch = jules.list_activities(&session, None, None).unwrap();
+        let reason = batch.activities.iter().find_map(|a| match &a.kind {
+            ActivityKind::SessionFailed { reason } => Some(reason.as_str()),
+            _ => None,
+        });
+        assert_eq!(reason, Some("Mock failure: tests did not pass"));
+    }
+
+    #[test]
+    fn cancel_stops_the_session_without_deleting_it() {
+        let server = MockJulesServer::start(Scenario::HappyPath).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Add a main function", true);
+        poll_until(&jules, &session, "waiting_approval");
+
+        jules.cancel_session(&session).unwrap();
+        // The DELETE fallback would have removed the session
+        let (status, pr) = jules.poll_session(&session).unwrap();
+        assert_eq!(status.key(), "failed");
+        assert!(pr.is_none());
+        // An ended session can't be cancelled again
+        assert!(jules.cancel_session(&session).is_err());
+    }
+
+    #[test]
+    fn a_session_without_automatic_prs_completes_without_one() {
+        let server = MockJulesServer::start(Scenario::HappyPath).unwrap();
+        let jules = client(&server);
+        let options = SessionOptions {
+            automation_mode: crate::jules::AutomationMode::AutomationModeUnspecified,
+            ..SessionOptions::default()
+        };
+        let session = jules.start_session(SOURCE, "Look around", &options).unwrap();
+        assert!(poll_until(&jules, &session, "completed").is_none());
+    }
+
+    #[test]
+    fn activities_are_paged_and_resumed_from_the_cursor() {
+        let server = MockJulesServer::start(Scenario::Chatty).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Do many things", false);
+        poll_until(&jules, &session, "working");
+
+        // 253 activities: a progress update, the plan and its approval, 250 updates
+        let first = jules.list_activities(&session, None, None).unwrap();
+        assert_eq!(first.activities.len(), 253);
+        assert_eq!(first.cursor.as_deref(), Some("act-253"));
+        assert_eq!(first.page_token.as_deref(), Some("o+200"));
+
+        let again = jules.list_activities(&session, first.cursor.as_deref(), first.page_token.as_deref()).unwrap();
+        assert!(again.activities.is_empty());
+        assert_eq!(again.cursor, first.cursor);
+        assert_eq!(again.page_token, first.page_token);
+
+        poll_until(&jules, &session, "pr_ready");
+        let last = jules.list_activities(&session, again.cursor.as_deref(), again.page_token.as_deref()).unwrap();
+        assert_eq!(last.activities.len(), 1);
+        assert!(matches!(last.activities[0].kind, ActivityKind::SessionCompleted));
+        assert_eq!(last.cursor.as_deref(), Some("act-254"));
+    }
+
+    #[test]
+    fn a_cursor_on_a_later_page_is_found_without_a_page_token() {
+        let server = MockJulesServer::start(Scenario::Chatty).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Do many things", false);
+        poll_until(&jules, &session, "working");
+
+        let batch = jules.list_activities(&session, Some("act-150"), None).unwrap();
+        assert_eq!(batch.activities.len(), 103);
+        assert_eq!(batch.activities[0].id, "act-151");
+    }
+
+    #[test]
+    fn a_lost_cursor_skips_ahead_instead_of_replaying() {
+        let server = MockJulesServer::start(Scenario::HappyPath).unwrap();
+        let jules = client(&server);
+        let session = start(&jules, "Add a main function", false);
+        poll_until(&jules, &session, "working");
+
+        let batch = jules.list_activities(&session, Some("act-gone"), None).unwrap();
+        assert!(batch.activities.is_empty());
+        let newest = batch.cursor.clone().unwrap();
+        assert_ne!(newest, "act-gone");
+
+        poll_until(&jules, &session, "pr_ready");
+        let batch = jules.list_activities(&session, Some(&newest), batch.page_token.as_deref()).unwrap();
+        assert!(!batch.activities.is_empty());
+        assert!(batch.activities.iter().all(|a| a.id != newest));
+    }
+}
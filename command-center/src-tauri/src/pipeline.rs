@@ -0,0 +1,228 @@
+use crate::batch::GithubPermit;
+use crate::github::GithubClient;
+use crate::guards::{CodespaceGuard, DeployKeyGuard};
+use crate::jules::{JulesClient, JulesConfig};
+use crate::poller::emit_update;
+use crate::scheduler::SlotGuard;
+use crate::ssh_utils::SshKeypair;
+use crate::types::{AgentMode, JobFailure, JobStatus, JobUpdateEvent, PartialArtifacts};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::Manager;
+
+// A job is an ordered list of steps sharing one JobContext. The engine reports
+// each step as it starts, and when the job is cancelled or a step fails it
+// compensates the steps that ran, newest first.
+
+/// Everything a step may read or produce. Steps record what they create here
+/// so later steps, and compensation, can find it.
+pub struct JobContext {
+    pub app: tauri::AppHandle,
+    pub job_id: String,
+    pub cancel: Arc<AtomicBool>,
+    pub gh: GithubClient,
+    pub jules: JulesClient,
+    pub mode: AgentMode,
+    pub owner: String,
+    pub repo: String,
+    pub created_repo: Option<String>, // Set when the job made the repo itself
+    pub codespace: Option<CodespaceGuard>,
+    pub keys: Option<SshKeypair>,
+    pub deploy_key: Option<DeployKeyGuard>,
+    pub session_id: Option<String>,
+    pub slot: Option<SlotGuard>, // Held from session start until polling ends
+    pub github_permit: Option<GithubPermit>, // Batch children only, until session start
+}
+
+impl JobContext {
+    /// Must be called on a blocking thread: it creates the blocking HTTP clients.
+    pub fn new(app: tauri::AppHandle, job_id: String, gh_token: String, jules_config: JulesConfig, mode: AgentMode) -> Self {
+        let state = app.state::<crate::AppState>();
+        let cancel = state.cancel_token(&job_id);
+        // cancel_job only reaches jobs whose flag exists, so catch one cancelled before this
+        if state.jobs.get(&job_id).is_some_and(|job| job.status == JobStatus::Cancelled) {
+            cancel.store(true, Ordering::SeqCst);
+        }
+        Self::build(app, job_id, cancel, gh_token, jules_config, mode)
+    }
+
+    /// For dry runs: a throwaway job id whose cancel flag nothing else can reach.
+    pub fn for_dry_run(app: tauri::AppHandle, gh_token: String, jules_config: JulesConfig, mode: AgentMode) -> Self {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        Self::build(app, job_id, Arc::default(), gh_token, jules_config, mode)
+    }
+
+    fn build(app: tauri::AppHandle, job_id: String, cancel: Arc<AtomicBool>, gh_token: String, jules_config: JulesConfig, mode: AgentMode) -> Self {
+        let gh = GithubClient::new(gh_token).with_cache(app.state::<crate::AppState>().github_cache.clone());
+        JobContext {
+            app: app.clone(),
+            job_id,
+            cancel,
+            gh,
+            jules: JulesClient::new(jules_config),
+            mode,
+            owner: String::new(),
+            repo: String::new(),
+            created_repo: None,
+            codespace: None,
+            keys: None,
+            deploy_key: None,
+            session_id: None,
+            slot: None,
+            github_permit: None,
+        }
+    }
+
+    pub fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+pub trait Step: Send {
+    /// Stable identifier reported in failures, e.g. "create_codespace".
+    fn name(&self) -> &'static str;
+
+    /// Log line shown when the step starts, e.g. "Creating codespace...".
+    fn describe(&self) -> String;
+
+    /// The job status while this step runs. None keeps the current one.
+    fn status(&self) -> Option<JobStatus> {
+        None
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String>;
+
+    /// What `run` would do, for dry runs. May read but never change anything,
+    /// and fills in the context like `run` so later steps can plan too.
+    /// Returns detail lines for the report.
+    fn plan(&self, _ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    /// Undoes `run`. Also called for a step that failed part way, so it must
+    /// only touch what the context says exists. Returns log lines.
+    fn compensate(&self, _ctx: &mut JobContext) -> Vec<String> {
+        vec![]
+    }
+}
+
+pub type Pipeline = Vec<Box<dyn Step>>;
+
+fn compensate(ctx: &mut JobContext, steps: &[Box<dyn Step>]) -> Vec<String> {
+    steps.iter().rev().flat_map(|step| step.compensate(ctx)).collect()
+}
+
+// Network trouble, rate limits and 5xx responses are worth retrying; anything
+// else needs a change first. Errors format HTTP statuses as "...: 503 Service Unavailable".
+pub fn is_retryable(error: &str) -> bool {
+    const PATTERNS: [&str; 9] = [
+        "error sending request", "timed out", "connection", "limit reached",
+        ": 429", ": 500", ": 502", ": 503", ": 504",
+    ];
+    let error = error.to_lowercase();
+    PATTERNS.iter().any(|pattern| error.contains(pattern))
+}
+
+pub fn run(mut ctx: JobContext, steps: Pipeline, initial: JobStatus) -> Result<(), JobFailure> {
+    let mut status = initial;
+
+    for (i, step) in steps.iter().enumerate() {
+        if ctx.cancelled() {
+            let mut logs = vec!["Cancellation requested, rolling back...".to_string()];
+            logs.extend(compensate(&mut ctx, &steps[..i]));
+            emit_update(&ctx.app, JobUpdateEvent::new(&ctx.job_id, JobStatus::Cancelled, logs));
+            return Ok(());
+        }
+
+        if let Some(s) = step.status() {
+            status = s;
+        }
+        emit_update(&ctx.app, JobUpdateEvent::new(&ctx.job_id, status.clone(), vec![step.describe()]));
+
+        // A panicking step fails like any other, so it is named and compensated
+        let result = panic::catch_unwind(AssertUnwindSafe(|| step.run(&mut ctx)))
+            .unwrap_or_else(|payload| Err(format!("Internal error: {}", panic_message(payload))));
+        if let Err(e) = result {
+            // A step interrupted by cancel_job is a cancellation, not a failure
+            if ctx.cancelled() {
+                let mut logs = vec!["Cancellation requested, rolling back...".to_string()];
+                logs.extend(compensate(&mut ctx, &steps[..=i]));
+                emit_update(&ctx.app, JobUpdateEvent::new(&ctx.job_id, JobStatus::Cancelled, logs));
+                return Ok(());
+            }
+
+            // Captured before compensation clears the context
+            let artifacts = Box::new(PartialArtifacts {
+                created_repo: ctx.created_repo.clone(),
+                session_id: ctx.session_id.clone(),
+                pr_details: None,
+            });
+            let logs = compensate(&mut ctx, &steps[..=i]);
+            if !logs.is_empty() {
+                emit_update(&ctx.app, JobUpdateEvent::new(&ctx.job_id, status, logs));
+            }
+            return Err(JobFailure { artifacts, ..JobFailure::at_step(step.name(), e) });
+        }
+    }
+    Ok(())
+}
+
+impl JobFailure {
+    /// A failure before any step ran, e.g. invalid input.
+    pub fn before_steps(error: String) -> Self {
+        JobFailure { retryable: false, error, step: None, artifacts: Box::default() }
+    }
+
+    pub fn at_step(step: &str, error: String) -> Self {
+        JobFailure { retryable: is_retryable(&error), error, step: Some(step.to_string()), artifacts: Box::default() }
+    }
+}
+
+/// Sets the job to Failed and tells the UI why.
+pub fn report_failure(app: &tauri::AppHandle, job_id: &str, mut failure: JobFailure) {
+    app.state::<crate::AppState>().update_job(job_id, |job| {
+        // Whatever the job recorded before dying counts, even if the step didn't say
+        if failure.artifacts.session_id.is_none() {
+            failure.artifacts.session_id = job.jules_session_id.clone();
+        }
+        failure.artifacts.pr_details = job.pr_details.clone();
+        job.failure = Some(failure.clone());
+    });
+
+    let mut logs = vec![match &failure.step {
+        Some(step) => format!("Job failed at {}: {}", step, failure.error),
+        None => format!("Job failed: {}", failure.error),
+    }];
+    if let Some(repo) = &failure.artifacts.created_repo {
+        logs.push(format!("Repository {} was created and has been kept.", repo));
+    }
+    if failure.retryable {
+        logs.push("This looks temporary; starting the job again may succeed.".to_string());
+    }
+
+    let status = JobStatus::Failed { reason: failure.error.clone() };
+    emit_update(app, JobUpdateEvent { failure: Some(failure), ..JobUpdateEvent::new(job_id, status, logs) });
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Runs a job on a blocking thread. Errors and panics both end in report_failure;
+/// run() already turns a panic inside a step into that step's failure.
+pub fn spawn(app: tauri::AppHandle, job_id: String, job: impl FnOnce() -> Result<(), JobFailure> + Send + 'static) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(job));
+        app.state::<crate::AppState>().forget(&job_id);
+        let failure = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(failure)) => failure,
+            Err(payload) => JobFailure::before_steps(format!("Internal error: {}", panic_message(payload))),
+        };
+        report_failure(&app, &job_id, failure);
+    });
+}
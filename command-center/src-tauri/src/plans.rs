@@ -0,0 +1,175 @@
+use crate::types::{Plan, PlanStep};
+use serde::Serialize;
+
+// Plan revisions are compared step by step so the UI can highlight what changed
+// after refine_agent_plan feedback. Steps are matched on their title, so an
+// inserted step doesn't shift every later one, and failing that on their index.
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepChange {
+    Unchanged,
+    Added,
+    Modified,
+    Removed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StepDiff {
+    pub step: PlanStep,
+    pub change: StepChange,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanRevision {
+    pub revision: usize, // 1-based
+    pub plan: Plan,
+    pub steps: Vec<StepDiff>, // Against the previous revision; all Added for the first
+}
+
+pub fn diff(previous: Option<&Plan>, next: &Plan) -> Vec<StepDiff> {
+    let old_steps: &[PlanStep] = previous.map(|p| p.steps.as_slice()).unwrap_or(&[]);
+
+    // matches[i] is the old step paired with next.steps[i]
+    let mut matched = vec![false; old_steps.len()];
+    let mut matches: Vec<Option<usize>> = next.steps.iter().map(|step| {
+        let found = (0..old_steps.len()).find(|&j| !matched[j] && old_steps[j].title == step.title);
+        if let Some(j) = found {
+            matched[j] = true;
+        }
+        found
+    }).collect();
+    for (step, found) in next.steps.iter().zip(matches.iter_mut()).filter(|(_, found)| found.is_none()) {
+        *found = (0..old_steps.len()).find(|&j| !matched[j] && old_steps[j].index == step.index);
+        if let Some(j) = *found {
+            matched[j] = true;
+        }
+    }
+
+    let mut diffs: Vec<StepDiff> = next.steps.iter().zip(matches).map(|(step, old)| {
+        let change = match old.map(|j| &old_steps[j]) {
+            None => StepChange::Added,
+            Some(old) if old.title == step.title && old.description == step.description => StepChange::Unchanged,
+            Some(_) => StepChange::Modified,
+        };
+        StepDiff { step: step.clone(), change }
+    }).collect();
+
+    for (old, _) in old_steps.iter().zip(matched).filter(|(_, matched)| !matched) {
+        diffs.push(StepDiff { step: old.clone(), change: StepChange::Removed });
+    }
+    diffs
+}
+
+pub fn revisions(plans: &[Plan]) -> Vec<PlanRevision> {
+    plans.iter().enumerate().map(|(i, plan)| PlanRevision {
+        revision: i + 1,
+        plan: plan.clone(),
+        steps: diff(i.checked_sub(1).map(|prev| &plans[prev]), plan),
+    }).collect()
+}
+
+/// One-line summary for the job log, e.g. "1 modified, 1 added".
+pub fn summarize(diffs: &[StepDiff]) -> String {
+    let count = |c: StepChange| diffs.iter().filter(|d| d.change == c).count();
+    let parts: Vec<String> = [
+        (count(StepChange::Modified), "modified"),
+        (count(StepChange::Added), "added"),
+        (count(StepChange::Removed), "removed"),
+    ].iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{} {}", n, label))
+        .collect();
+
+    if parts.is_empty() {
+        "no step changes".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(steps: &[(&str, &str)]) -> Plan {
+        Plan {
+            id: "plan".to_string(),
+            steps: steps.iter().enumerate().map(|(i, (title, description))| PlanStep {
+                index: i as u32,
+                title: title.to_string(),
+                description: description.to_string(),
+            }).collect(),
+            create_time: String::new(),
+        }
+    }
+
+    fn changes(diffs: &[StepDiff]) -> Vec<(&str, StepChange)> {
+        diffs.iter().map(|d| (d.step.title.as_str(), d.change.clone())).collect()
+    }
+
+    #[test]
+    fn diffs_match_steps_by_title_then_index() {
+        use StepChange::*;
+        let base = plan(&[("Read", "r"), ("Write", "w"), ("Test", "t")]);
+        let cases: Vec<(Plan, Vec<(&str, StepChange)>)> = vec![
+            (base.clone(), vec![("Read", Unchanged), ("Write", Unchanged), ("Test", Unchanged)]),
+            (
+                plan(&[("Plan", "p"), ("Read", "r"), ("Write", "w"), ("Test", "t")]),
+                vec![("Plan", Added), ("Read", Unchanged), ("Write", Unchanged), ("Test", Unchanged)],
+            ),
+            (plan(&[("Read", "r"), ("Test", "t")]), vec![("Read", Unchanged), ("Test", Unchanged), ("Write", Removed)]),
+            (
+                plan(&[("Read", "r"), ("Write", "w, with docs"), ("Test", "t")]),
+                vec![("Read", Unchanged), ("Write", Modified), ("Test", Unchanged)],
+            ),
+            (
+                plan(&[("Read", "r"), ("Write code", "w"), ("Test", "t")]),
+                vec![("Read", Unchanged), ("Write code", Modified), ("Test", Unchanged)],
+            ),
+            (
+                plan(&[("Test", "t"), ("Read", "r"), ("Write", "w")]),
+                vec![("Test", Unchanged), ("Read", Unchanged), ("Write", Unchanged)],
+            ),
+            (
+                plan(&[("Read", "r"), ("Write", "w"), ("Test", "t"), ("Ship", "s")]),
+                vec![("Read", Unchanged), ("Write", Unchanged), ("Test", Unchanged), ("Ship", Added)],
+            ),
+        ];
+        for (next, expected) in cases {
+            assert_eq!(changes(&diff(Some(&base), &next)), expected);
+        }
+    }
+
+    #[test]
+    fn repeated_titles_pair_up_in_order() {
+        let old = plan(&[("Fix", "a"), ("Fix", "b")]);
+        let next = plan(&[("Fix", "a"), ("Fix", "c"), ("Fix", "d")]);
+        let diffs = diff(Some(&old), &next);
+        assert_eq!(changes(&diffs), vec![("Fix", StepChange::Unchanged), ("Fix", StepChange::Modified), ("Fix", StepChange::Added)]);
+    }
+
+    #[test]
+    fn the_first_revision_is_all_added() {
+        let revisions = revisions(&[plan(&[("Read", "r")]), plan(&[("Plan", "p"), ("Read", "r")])]);
+        assert_eq!(revisions[0].revision, 1);
+        assert_eq!(changes(&revisions[0].steps), vec![("Read", StepChange::Added)]);
+        assert_eq!(changes(&revisions[1].steps), vec![("Plan", StepChange::Added), ("Read", StepChange::Unchanged)]);
+    }
+
+    #[test]
+    fn summaries_count_each_kind_of_change() {
+        let base = plan(&[("Read", "r"), ("Write", "w"), ("Test", "t")]);
+        let cases = [
+            (base.clone(), "no step changes"),
+            (plan(&[("Plan", "p"), ("Read", "r"), ("Write", "w"), ("Test", "t")]), "1 added"),
+            (plan(&[("Read", "r2"), ("Write", "w2")]), "2 modified, 1 removed"),
+            (plan(&[("Read", "r"), ("Test", "t2"), ("Ship", "s"), ("Docs", "d")]), "1 modified, 2 added, 1 removed"),
+        ];
+        for (next, expected) in cases {
+            assert_eq!(summarize(&diff(Some(&base), &next)), expected);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::batch;
+use crate::jules::{self, JulesClient};
+use crate::messaging;
+use crate::plans;
+use crate::types::{ActivityKind, JobStatus, JobUpdateEvent};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Shared by every job type: keeps AppState in sync with what is sent to the UI,
+// and follows a Jules session until Jules is done with it.
+
+pub fn emit_update(app: &tauri::AppHandle, mut event: JobUpdateEvent) {
+    let mut batch_changed = None;
+    app.state::<crate::AppState>().update_job(&event.id, |job| {
+        if job.approval_pending && !matches!(event.status, JobStatus::WaitingApproval) {
+            job.approval_pending = false;
+            event.logs.push("Plan approval confirmed by Jules.".to_string());
+        }
+        // Cancellation wins over whatever the engine was about to report
+        if job.status == JobStatus::Cancelled {
+            event.status = JobStatus::Cancelled;
+        }
+        if job.status != event.status {
+            batch_changed = job.batch_id.clone();
+        }
+        job.status = event.status.clone();
+        if event.pr_details.is_some() {
+            job.pr_details = event.pr_details.clone();
+        }
+    });
+    app.state::<crate::AppState>().jobs.append_logs(&event.id, &event.logs);
+
+    if let Err(e) = app.emit("JOB_UPDATE", event) {
+        println!("Failed to emit event: {}", e);
+    }
+    if let Some(batch_id) = batch_changed {
+        batch::emit_progress(app, &batch_id);
+    }
+}
+
+/// Records a non-fatal problem on the job and shows it in the log.
+/// Called from guard drops, possibly while a panic unwinds, so it must not panic itself.
+pub fn emit_warning(app: &tauri::AppHandle, job_id: &str, warning: &str) {
+    let state = app.state::<crate::AppState>();
+    let Some(status) = state.jobs.update(job_id, |job| {
+        job.warnings.push(warning.to_string());
+        job.status.clone()
+    }) else { return };
+    let event = JobUpdateEvent::new(job_id, status, vec![format!("[warning] {}", warning)]);
+    state.jobs.append_logs(job_id, &event.logs);
+
+    if let Err(e) = app.emit("JOB_UPDATE", event) {
+        println!("Failed to emit event: {}", e);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PollPolicy {
+    pub interval_secs: u64,      // Starting interval, and the interval after any change
+    pub max_interval_secs: u64,  // Backoff ceiling while nothing changes
+    pub backoff: f64,            // Multiplier per unchanged poll
+    pub fast_interval_secs: u64, // Right after a user action
+    pub fast_polls: u32,         // How many fast polls a user action buys
+    pub max_runtime_secs: u64,   // Give up after this much polling, not counting time spent waiting on the user
+    pub max_consecutive_errors: u32,
+    pub jitter: f64,             // +/- fraction of each interval, spreads out batches
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        PollPolicy {
+            interval_secs: 5,
+            max_interval_secs: 60,
+            backoff: 1.5,
+            fast_interval_secs: 2,
+            fast_polls: 5,
+            max_runtime_secs: 6 * 60 * 60,
+            max_consecutive_errors: 10,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl PollPolicy {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        app.store(APP_STORE).ok()
+            .and_then(|store| store.get(POLL_POLICY_KEY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) -> Result<(), String> {
+        if self.interval_secs == 0 || self.max_interval_secs < self.interval_secs || self.backoff < 1.0 {
+            return Err("Invalid poll policy".to_string());
+        }
+        let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+        store.set(POLL_POLICY_KEY, serde_json::json!(self));
+        store.save().map_err(|e| e.to_string())
+    }
+
+    fn jittered(&self, secs: f64) -> Duration {
+        let spread = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + rand::thread_rng().gen_range(-spread..=spread);
+        Duration::from_secs_f64((secs * factor).max(0.5))
+    }
+}
+
+/// How long to wait between polls and when to give up on errors. Kept apart
+/// from the poll loop so it can be tested without a session.
+struct Pacing {
+    policy: PollPolicy,
+    interval: f64, // Seconds, before jitter
+    fast_polls_left: u32,
+    errors: u32,
+}
+
+impl Pacing {
+    fn new(policy: PollPolicy) -> Self {
+        Pacing { policy, interval: policy.interval_secs as f64, fast_polls_left: 0, errors: 0 }
+    }
+
+    /// A user action: the next few polls come quickly.
+    fn nudged(&mut self) {
+        self.fast_polls_left = self.policy.fast_polls;
+    }
+
+    /// Back off while nothing happens, snap back as soon as something does.
+    fn polled(&mut self, changed: bool) {
+        self.errors = 0;
+        self.interval = if changed {
+            self.policy.interval_secs as f64
+        } else {
+            (self.interval * self.policy.backoff).min(self.policy.max_interval_secs as f64)
+        };
+    }
+
+    /// Backs off and returns false once the error budget is used up.
+    fn failed(&mut self) -> bool {
+        self.errors += 1;
+        self.interval = (self.interval * self.policy.backoff).min(self.policy.max_interval_secs as f64);
+        self.errors < self.policy.max_consecutive_errors
+    }
+
+    fn next_wait(&mut self) -> Duration {
+        if self.fast_polls_left > 0 {
+            self.fast_polls_left -= 1;
+            self.policy.jittered(self.policy.fast_interval_secs as f64)
+        } else {
+            self.policy.jittered(self.interval)
+        }
+    }
+}
+
+// Time Jules spends waiting on the user doesn't count against max_runtime_secs
+fn waiting_on_user(status: &JobStatus) -> bool {
+    matches!(status, JobStatus::WaitingApproval | JobStatus::WaitingFeedback | JobStatus::Paused)
+}
+
+const POLL_POLICY_KEY: &str = "poll_policy";
+const TICK: Duration = Duration::from_millis(250);
+
+/// Polls until Jules is done with the session or the job is cancelled.
+/// Errs when the policy's runtime or error budget runs out, or with Jules's
+/// reason when the session fails. The runtime is kept on the job, so it
+/// carries over when polling resumes after a restart.
+pub fn poll_until_done(app_handle: &tauri::AppHandle, jules: &JulesClient, job_id: &str, session_id: &str) -> Result<(), String> {
+    let state = app_handle.state::<crate::AppState>();
+    let cancel = state.cancel_token(job_id);
+    let nudge = state.nudge_token(job_id);
+    let policy = PollPolicy::load(app_handle);
+    let mut pacing = Pacing::new(policy);
+    let mut last_status: Option<JobStatus> = None;
+    let job = state.jobs.get(job_id);
+    let (mut cursor, mut page_token) = job.as_ref()
+        .map(|job| (job.activity_cursor.clone(), job.activity_page_token.clone()))
+        .unwrap_or_default();
+    let mut runtime = Duration::from_secs(job.as_ref().map_or(0, |job| job.poll_runtime_secs));
+    let mut counting = !job.is_some_and(|job| waiting_on_user(&job.status));
+    let mut last_checked = Instant::now();
+
+    // The first poll lands anywhere in the first interval so jobs started together don't poll together
+    let mut wait = Duration::from_secs_f64(policy.interval_secs as f64 * rand::thread_rng().gen_range(0.0..=1.0));
+
+    loop {
+        let deadline = Instant::now() + wait;
+        while Instant::now() < deadline {
+            // cancel_job has already stopped the remote session
+            if cancel.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            if nudge.swap(false, Ordering::SeqCst) {
+                pacing.nudged();
+                break;
+            }
+            thread::sleep(TICK);
+        }
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if counting {
+            runtime += last_checked.elapsed();
+            state.update_job(job_id, |job| job.poll_runtime_secs = runtime.as_secs());
+        }
+        last_checked = Instant::now();
+        if runtime > Duration::from_secs(policy.max_runtime_secs) {
+            return Err(format!(
+                "Gave up after {} minutes without the session finishing",
+                policy.max_runtime_secs / 60
+            ));
+        }
+
+        match jules.poll_session(session_id) {
+            Ok((mut status, pr)) => {
+                state.update_job(job_id, |job| job.last_poll = Some(chrono::Utc::now().timestamp() as u64));
+
+                // Only activities after the cursor are new since the last poll
+                let activities = match jules.list_activities(session_id, cursor.as_deref(), page_token.as_deref()) {
+                    Ok(batch) => {
+                        if batch.cursor != cursor || batch.page_token != page_token {
+                            cursor = batch.cursor;
+                            page_token = batch.page_token;
+                            state.update_job(job_id, |job| {
+                                job.activity_cursor = cursor.clone();
+                                job.activity_page_token = page_token.clone();
+                            });
+                        }
+                        batch.activities
+                    }
+                    Err(e) => {
+                        println!("Failed to list activities: {}", e);
+                        // The saved page may have expired; the cursor alone still prevents repeats
+                        page_token = None;
+                        vec![]
+                    }
+                };
+
+                // The session resource has no failure reason; the activity stream does,
+                // possibly in an earlier poll than the one that sees the session fail
+                for activity in &activities {
+                    if let ActivityKind::SessionFailed { reason } = &activity.kind {
+                        state.update_job(job_id, |job| job.session_failure = Some(reason.clone()));
+                    }
+                }
+                if let JobStatus::Failed { reason } = &mut status {
+                    if let Some(r) = state.jobs.get(job_id).and_then(|job| job.session_failure) {
+                        *reason = r;
+                    }
+                }
+
+                let mut logs = vec![];
+                let status_changed = last_status.as_ref() != Some(&status);
+                if status_changed {
+                    match &status {
+                        JobStatus::Planning => logs.push("Jules is planning...".to_string()),
+                        JobStatus::Working => logs.push("Jules is working on code...".to_string()),
+                        JobStatus::WaitingApproval => logs.push("Plan ready for review.".to_string()),
+                        JobStatus::WaitingFeedback => logs.push("Jules is waiting for your reply.".to_string()),
+                        JobStatus::Paused => logs.push("Jules session paused.".to_string()),
+                        JobStatus::PrReady => logs.push("Pull Request created.".to_string()),
+                        JobStatus::Completed => logs.push("Jules finished without opening a pull request.".to_string()),
+                        _ => {}
+                    }
+                    last_status = Some(status.clone());
+                }
+                logs.extend(activities.iter().flat_map(jules::describe_activity));
+
+                // Keep every plan revision; a replay after restart must not duplicate them
+                let mut plan = None;
+                for activity in &activities {
+                    if let ActivityKind::PlanGenerated { plan: p } = &activity.kind {
+                        state.update_job(job_id, |job| {
+                            if job.plans.contains(p) {
+                                return;
+                            }
+                            if let Some(previous) = job.plans.last() {
+                                logs.push(format!(
+                                    "Plan revised (revision {}): {}",
+                                    job.plans.len() + 1,
+                                    plans::summarize(&plans::diff(Some(previous), p))
+                                ));
+                            }
+                            job.plans.push(p.clone());
+                        });
+                        plan = Some(p.clone());
+                    }
+                }
+                logs.extend(messaging::check_acknowledgements(app_handle, job_id, &activities));
+
+                let changed = status_changed || !activities.is_empty();
+                emit_update(app_handle, JobUpdateEvent {
+                    pr_details: pr,
+                    plan,
+                    activities,
+                    ..JobUpdateEvent::new(job_id, status.clone(), logs)
+                });
+
+                // The caller reports it like any other failure, so it is recorded on the job
+                if let JobStatus::Failed { reason } = &status {
+                    return Err(format!("Jules session failed: {}", reason));
+                }
+                if status.ends_session() {
+                    return Ok(());
+                }
+                counting = !waiting_on_user(&status);
+                pacing.polled(changed);
+            }
+            Err(e) => {
+                let retry = pacing.failed();
+                println!("Polling error ({}/{}): {}", pacing.errors, policy.max_consecutive_errors, e);
+                if !retry {
+                    return Err(format!("Polling failed {} times in a row: {}", pacing.errors, e));
+                }
+            }
+        }
+
+        wait = pacing.next_wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> PollPolicy {
+        PollPolicy { jitter: 0.0, ..PollPolicy::default() }
+    }
+
+    #[test]
+    fn backs_off_while_unchanged_and_snaps_back_on_change() {
+        let mut pacing = Pacing::new(policy());
+        assert_eq!(pacing.next_wait(), Duration::from_secs(5));
+
+        let waits: Vec<u64> = (0..8).map(|_| {
+            pacing.polled(false);
+            pacing.next_wait().as_secs()
+        }).collect();
+        assert_eq!(waits, [7, 11, 16, 25, 37, 56, 60, 60]);
+
+        pacing.polled(true);
+        assert_eq!(pacing.next_wait(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn a_nudge_buys_fast_polls_then_resumes_the_interval() {
+        let mut pacing = Pacing::new(PollPolicy { fast_polls: 2, ..policy() });
+        pacing.polled(false);
+        pacing.nudged();
+        assert_eq!(pacing.next_wait(), Duration::from_secs(2));
+        assert_eq!(pacing.next_wait(), Duration::from_secs(2));
+        assert_eq!(pacing.next_wait(), Duration::from_secs_f64(7.5));
+    }
+
+    #[test]
+    fn errors_back_off_until_the_budget_runs_out() {
+        let mut pacing = Pacing::new(PollPolicy { max_consecutive_errors: 3, ..policy() });
+        assert!(pacing.failed());
+        assert!(pacing.failed());
+        // A successful poll resets the budget
+        pacing.polled(true);
+        assert!(pacing.failed());
+        assert!(pacing.failed());
+        assert_eq!(pacing.next_wait(), Duration::from_secs_f64(11.25));
+        assert!(!pacing.failed());
+    }
+
+    #[test]
+    fn jitter_stays_within_its_fraction_and_above_the_floor() {
+        let jittery = PollPolicy { jitter: 0.2, ..PollPolicy::default() };
+        for _ in 0..200 {
+            let wait = jittery.jittered(10.0).as_secs_f64();
+            assert!((8.0..=12.0).contains(&wait), "{}", wait);
+        }
+        assert_eq!(policy().jittered(10.0), Duration::from_secs(10));
+        // Out of range jitter is clamped, and a wait never drops below half a second
+        let wild = PollPolicy { jitter: 5.0, ..PollPolicy::default() };
+        for _ in 0..200 {
+            assert!(wild.jittered(1.0) >= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn only_user_waits_pause_the_runtime() {
+        assert!(waiting_on_user(&JobStatus::WaitingApproval));
+        assert!(waiting_on_user(&JobStatus::WaitingFeedback));
+        assert!(waiting_on_user(&JobStatus::Paused));
+        assert!(!waiting_on_user(&JobStatus::Planning));
+        assert!(!waiting_on_user(&JobStatus::Working));
+    }
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Task prompts sent to Jules. Every prompt is a template: the variables below
+// are expanded by StartSession, once the repo and its default branch are
+// known. The library is the built-in templates plus any the user saved.
+
+const TEMPLATES_KEY: &str = "prompt_templates";
+
+pub const VARIABLES: [&str; 3] = ["repo", "recipe", "default_branch"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    #[serde(default)]
+    pub id: String, // Empty when saving a new template
+    pub name: String,
+    pub body: String,
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+fn builtins() -> Vec<PromptTemplate> {
+    let builtin = |id: &str, name: &str, body: &str| PromptTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        body: body.to_string(),
+        builtin: true,
+    };
+    vec![
+        builtin("scaffold-review", "Review scaffold", crate::scaffold_engine::DEFAULT_PROMPT),
+        builtin("uplink-agents", "Follow AGENTS.md", crate::uplink_engine::DEFAULT_PROMPT),
+        builtin("add-ci", "Add CI", "Add a GitHub Actions workflow to {repo} that builds and tests the project on pushes and pull requests to {default_branch}."),
+    ]
+}
+
+fn saved<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<PromptTemplate> {
+    app.store(APP_STORE).ok()
+        .and_then(|store| store.get(TEMPLATES_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn write<R: tauri::Runtime>(app: &tauri::AppHandle<R>, templates: &[PromptTemplate]) -> Result<(), String> {
+    let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+    store.set(TEMPLATES_KEY, serde_json::json!(templates));
+    store.save().map_err(|e| e.to_string())
+}
+
+pub fn list<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<PromptTemplate> {
+    let mut templates = builtins();
+    templates.extend(saved(app));
+    templates
+}
+
+/// Adds a template, or replaces the one with the same id. Returns it with its id.
+pub fn save<R: tauri::Runtime>(app: &tauri::AppHandle<R>, mut template: PromptTemplate) -> Result<PromptTemplate, String> {
+    if template.name.trim().is_empty() || template.body.trim().is_empty() {
+        return Err("A template needs a name and a body".to_string());
+    }
+    if builtins().iter().any(|b| b.id == template.id) {
+        return Err("Built-in templates can't be changed".to_string());
+    }
+    validate(&template.body)?;
+
+    if template.id.is_empty() {
+        template.id = uuid::Uuid::new_v4().to_string();
+    }
+    template.builtin = false;
+    let mut templates = saved(app);
+    match templates.iter_mut().find(|t| t.id == template.id) {
+        Some(existing) => *existing = template.clone(),
+        None => templates.push(template.clone()),
+    }
+    write(app, &templates)?;
+    Ok(template)
+}
+
+pub fn delete<R: tauri::Runtime>(app: &tauri::AppHandle<R>, id: &str) -> Result<(), String> {
+    if builtins().iter().any(|b| b.id == id) {
+        return Err("Built-in templates can't be deleted".to_string());
+    }
+    let mut templates = saved(app);
+    let before = templates.len();
+    templates.retain(|t| t.id != id);
+    if templates.len() == before {
+        return Err("Template not found".to_string());
+    }
+    write(app, &templates)
+}
+
+// "{name}" placeholders in the template, in order. Braces around anything that
+// isn't a plain identifier (code, JSON) are left alone.
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut found = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else { break };
+        let name = &rest[..end];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_') {
+            found.push(name);
+        }
+    }
+    found
+}
+
+/// Rejects placeholders that expand() wouldn't fill, which are almost always typos.
+pub fn validate(template: &str) -> Result<(), String> {
+    match placeholders(template).into_iter().find(|name| !VARIABLES.contains(name)) {
+        Some(name) => Err(format!(
+            "Unknown variable {{{}}}. Available: {}",
+            name,
+            VARIABLES.iter().map(|v| format!("{{{}}}", v)).collect::<Vec<_>>().join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Fills in the variables in one pass, so a value that itself looks like a
+/// placeholder is inserted as is.
+pub fn expand(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let value = rest.find('}').and_then(|end| {
+            vars.iter().find(|(name, _)| *name == &rest[..end]).map(|(_, value)| (end, *value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => out.push('{'),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: [(&str, &str); 3] = [("repo", "owner/app"), ("recipe", "rust-cli"), ("default_branch", "main")];
+
+    #[test]
+    fn placeholders_are_plain_identifiers_in_braces() {
+        let cases: [(&str, Vec<&str>); 7] = [
+            ("no variables", vec![]),
+            ("{repo} on {default_branch}", vec!["repo", "default_branch"]),
+            ("{repo} and {repo}", vec!["repo", "repo"]),
+            ("fn main() { println!(\"{}\", 1) }", vec![]),
+            ("{\"key\": \"value\"}", vec![]),
+            ("{{repo}}", vec!["repo"]),
+            ("unclosed {repo", vec![]),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(placeholders(template), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unknown_variables() {
+        assert!(validate("Add CI to {repo} on {default_branch} for {recipe}").is_ok());
+        assert!(validate("Code like { x: 1 } is fine").is_ok());
+
+        let err = validate("Add CI to {repository}").unwrap_err();
+        assert!(err.starts_with("Unknown variable {repository}."), "{}", err);
+        assert!(err.contains("{repo}, {recipe}, {default_branch}"), "{}", err);
+    }
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        for template in builtins() {
+            assert!(validate(&template.body).is_ok(), "{}", template.id);
+        }
+    }
+
+    #[test]
+    fn expand_fills_in_every_known_variable() {
+        let cases = [
+            ("Work on {repo}", "Work on owner/app"),
+            ("{repo}@{default_branch}, {repo} again", "owner/app@main, owner/app again"),
+            ("Keep {unknown} and { code }", "Keep {unknown} and { code }"),
+            ("{{recipe}}", "{rust-cli}"),
+            ("Trailing {", "Trailing {"),
+            ("", ""),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(expand(template, &VARS), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn expanded_values_are_not_expanded_again() {
+        let vars = [("repo", "{recipe}"), ("recipe", "rust-cli")];
+        assert_eq!(expand("{repo} uses {recipe}", &vars), "{recipe} uses rust-cli");
+    }
+}
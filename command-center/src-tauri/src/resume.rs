@@ -0,0 +1,174 @@
+use crate::auth;
+use crate::automerge;
+use crate::github::GithubClient;
+use crate::jules::{JulesClient, JulesConfig};
+use crate::pipeline::{self, JobContext};
+use crate::poller::{self, emit_update};
+use crate::scaffold_engine;
+use crate::sweeper::{self, OrphanKind};
+use crate::types::{JobFailure, JobParams, JobState, JobStatus, JobUpdateEvent};
+use crate::uplink_engine;
+use tauri::Manager;
+
+// Picks jobs back up after a restart. A job with a Jules session only needs
+// its poll loop again. One that died before that lost its SSH keys and its
+// place in the pipeline, so it waits in Interrupted until the user either
+// resumes it, which starts provisioning over, or rolls it back.
+
+/// Called once at startup, before anything else touches the jobs. Sessions are
+/// followed on their own threads, so this returns quickly.
+pub fn resume_all(app: &tauri::AppHandle) {
+    let jobs = app.state::<crate::AppState>().jobs.all();
+    let jules_config = auth::get_jules_config(app);
+
+    for job in jobs.into_iter().filter(|job| !job.status.ends_session()) {
+        let log = match (&job.jules_session_id, &jules_config) {
+            _ if job.status == JobStatus::Interrupted => "Interrupted by an earlier restart. Resume or roll back.",
+            (Some(session_id), Some(config)) => {
+                emit_update(app, JobUpdateEvent::new(&job.id, job.status.clone(), vec!["Reattached to Jules session after restart.".to_string()]));
+                follow_session(app, &job.id, session_id.clone(), config.clone());
+                continue;
+            }
+            (Some(_), None) => "Jules isn't signed in, so the session can't be followed. Resume once it is.",
+            (None, _) => "Interrupted by a restart before the Jules session started. Resume to start over, or roll back.",
+        };
+        emit_update(app, JobUpdateEvent::new(&job.id, JobStatus::Interrupted, vec![log.to_string()]));
+    }
+    // PrReady jobs have no session to follow, but may have had a merge waiting on checks
+    automerge::resume_all(app);
+}
+
+/// Polls an existing session on a blocking thread. The session counts against
+/// the concurrency limit, but not the daily one, until polling ends.
+pub fn follow_session(app: &tauri::AppHandle, job_id: &str, session_id: String, config: JulesConfig) {
+    let slot = app.state::<crate::AppState>().scheduler.adopt(job_id);
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _slot = slot;
+        let jules = JulesClient::new(config);
+        match poller::poll_until_done(&app, &jules, &job_id, &session_id) {
+            Ok(()) => {
+                if let Some(token) = auth::get_github_token(&app) {
+                    let gh = GithubClient::new(token).with_cache(app.state::<crate::AppState>().github_cache.clone());
+                    automerge::run(&app, &gh, &job_id);
+                }
+            }
+            Err(e) => pipeline::report_failure(&app, &job_id, JobFailure::at_step("poll_session", e)),
+        }
+        app.state::<crate::AppState>().forget(&job_id);
+    });
+}
+
+fn interrupted_job(app: &tauri::AppHandle, job_id: &str) -> Result<JobState, String> {
+    let job = app.state::<crate::AppState>().jobs.get(job_id).ok_or("Job not found")?;
+    if job.status != JobStatus::Interrupted {
+        return Err(format!("Job {} was not interrupted", job_id));
+    }
+    Ok(job)
+}
+
+// The status the job had before the restart, so the card doesn't jump back to the start
+fn status_before_interrupt(job: &JobState) -> JobStatus {
+    job.history.iter().rev()
+        .map(|change| change.status.clone())
+        .find(|status| *status != JobStatus::Interrupted)
+        .unwrap_or(JobStatus::Planning)
+}
+
+// Deletes the codespace and deploy key the interrupted run left behind. Runs
+// while the job is still Interrupted, since the sweeper skips provisioning jobs.
+fn release_leftovers(app: &tauri::AppHandle, gh: &GithubClient, job_id: &str) -> Vec<String> {
+    let leftovers = match sweeper::scan_for_app(app, gh) {
+        Ok(orphans) => orphans.into_iter().filter(|o| o.job_id.as_deref() == Some(job_id)).collect(),
+        Err(e) => return vec![format!("Couldn't check for leftover resources: {}", e)],
+    };
+    sweeper::sweep(app, gh, leftovers).into_iter().map(|result| {
+        let kind = match result.resource.kind {
+            OrphanKind::Codespace => "codespace",
+            OrphanKind::DeployKey => "deploy key",
+        };
+        match result.error {
+            None => format!("Deleted leftover {} {}.", kind, result.resource.resource_id),
+            Some(e) => format!("Failed to delete leftover {} {}: {}", kind, result.resource.resource_id, e),
+        }
+    }).collect()
+}
+
+/// Must be called on a blocking thread.
+pub fn resume_job(app: &tauri::AppHandle, job_id: &str) -> Result<(), String> {
+    let job = interrupted_job(app, job_id)?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
+
+    if let Some(session_id) = job.jules_session_id.clone() {
+        emit_update(app, JobUpdateEvent::new(job_id, status_before_interrupt(&job), vec!["Reattached to Jules session.".to_string()]));
+        follow_session(app, job_id, session_id, jules_config);
+        return Ok(());
+    }
+
+    let params = job.params.clone().ok_or("Job has no saved parameters to resume from")?;
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let state = app.state::<crate::AppState>();
+    let gh = GithubClient::new(gh_token.clone()).with_cache(state.github_cache.clone());
+    // A repo created by the interrupted run is reused rather than created again
+    let existing_repo = sweeper::created_repos(app).contains(&job.github_repo).then(|| job.github_repo.clone());
+
+    let mut logs = vec!["Resuming: starting provisioning over...".to_string()];
+    logs.extend(release_leftovers(app, &gh, job_id));
+    let initial = match params {
+        JobParams::Uplink { .. } => JobStatus::UploadingContext,
+        _ => JobStatus::Booting,
+    };
+    emit_update(app, JobUpdateEvent::new(job_id, initial.clone(), logs));
+
+    let handle = app.clone();
+    let id = job_id.to_string();
+    pipeline::spawn(app.clone(), job_id.to_string(), move || {
+        let (mode, steps, created_repo) = match params {
+            JobParams::Scaffold { name, recipe_id, context, mode, prompt, session } => {
+                let prompt = prompt.as_deref().unwrap_or(scaffold_engine::DEFAULT_PROMPT);
+                let steps = match existing_repo.as_ref().and_then(|full| full.split_once('/')) {
+                    Some((owner, repo)) => scaffold_engine::scaffold_steps_existing(owner, repo, &name, &recipe_id, &context, prompt, &session),
+                    None => scaffold_engine::scaffold_steps(&name, &recipe_id, &context, prompt, &session),
+                };
+                (mode, steps.map_err(JobFailure::before_steps)?, existing_repo)
+            }
+            JobParams::Uplink { repo_url, context, mode, prompt, session } => {
+                let (owner, repo) = uplink_engine::parse_repo_url(&repo_url).map_err(JobFailure::before_steps)?;
+                let prompt = prompt.as_deref().unwrap_or(uplink_engine::DEFAULT_PROMPT);
+                (mode, uplink_engine::uplink_steps(&owner, &repo, &context, prompt, &session), None)
+            }
+            JobParams::Import { .. } => {
+                return Err(JobFailure::before_steps("Imported session is missing its session id".to_string()));
+            }
+        };
+        let mut ctx = JobContext::new(handle, id, gh_token, jules_config, mode);
+        ctx.created_repo = created_repo;
+        pipeline::run(ctx, steps, initial)
+    });
+    Ok(())
+}
+
+/// Cleans up after an interrupted job and marks it cancelled. A repo it created
+/// is kept, as it is when a job fails. Must be called on a blocking thread.
+pub fn rollback_job(app: &tauri::AppHandle, job_id: &str) -> Result<(), String> {
+    let job = interrupted_job(app, job_id)?;
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let gh = GithubClient::new(gh_token).with_cache(app.state::<crate::AppState>().github_cache.clone());
+
+    let mut logs = vec!["Rolling back interrupted job...".to_string()];
+    logs.extend(release_leftovers(app, &gh, job_id));
+    if let Some(session_id) = &job.jules_session_id {
+        match auth::get_jules_config(app).map(|config| JulesClient::new(config).cancel_session(session_id)) {
+            Some(Ok(())) => logs.push("Cancelled Jules session.".to_string()),
+            Some(Err(e)) => logs.push(format!("Failed to cancel Jules session: {}", e)),
+            None => logs.push("Jules isn't signed in, so the session was left running.".to_string()),
+        }
+    }
+    if sweeper::created_repos(app).contains(&job.github_repo) {
+        logs.push(format!("Repository {} was created and has been kept.", job.github_repo));
+    }
+
+    emit_update(app, JobUpdateEvent::new(job_id, JobStatus::Cancelled, logs));
+    Ok(())
+}
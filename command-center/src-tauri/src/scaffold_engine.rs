@@ -1,10 +1,13 @@
-use crate::types::{AgentMode, JobStatus, JobUpdateEvent};
-use crate::github::GithubClient;
-use crate::ssh_utils::{generate_ephemeral_keypair, execute_ssh_command};
-use crate::jules::JulesClient;
-use std::thread;
-use std::time::Duration;
-use tauri::Emitter;
+use crate::auth;
+use crate::dry_run::{self, DryRunReport};
+use crate::prompts;
+use crate::types::{AgentMode, JobFailure, JobParams, JobState, JobStatus, SessionSettings};
+use crate::pipeline::{self, JobContext, Pipeline};
+use tauri::Manager;
+use crate::steps::{AddDeployKey, CreateCodespace, CreateRepo, PollSession, ReleaseWorkspace, RunGenerator, StartSession, VerifyAccess};
+
+/// Used when the job doesn't bring its own prompt.
+pub const DEFAULT_PROMPT: &str = "Review the generated code and make improvements.";
 
 pub fn resolve_recipe(recipe_id: &str) -> Result<String, String> {
     match recipe_id {
@@ -14,126 +17,85 @@ pub fn resolve_recipe(recipe_id: &str) -> Result<String, String> {
     }
 }
 
-fn emit_update(app: &tauri::AppHandle, event: JobUpdateEvent) {
-    if let Err(e) = app.emit("JOB_UPDATE", event) {
-        println!("Failed to emit event: {}", e);
-    }
+/// New repo -> codespace + deploy key -> recipe over SSH -> teardown -> Jules.
+pub fn scaffold_steps(name: &str, recipe_id: &str, context: &str, prompt: &str, session: &SessionSettings) -> Result<Pipeline, String> {
+    let recipe_url = resolve_recipe(recipe_id)?;
+    Ok(vec![
+        Box::new(CreateRepo { name: name.to_string() }),
+        Box::new(CreateCodespace),
+        Box::new(AddDeployKey),
+        Box::new(RunGenerator {
+            recipe_url,
+            name: name.to_string(),
+            context: context.to_string(),
+        }),
+        Box::new(ReleaseWorkspace),
+        Box::new(StartSession {
+            prompt: prompt.to_string(),
+            title: format!("Command Center: scaffold {}", name),
+            recipe: Some(recipe_id.to_string()),
+            session: session.clone(),
+        }),
+        Box::new(PollSession),
+    ])
 }
 
-pub fn run_scaffold_job(
-    job_id: String,
-    name: String,
-    recipe_id: String,
-    context: String,
-    mode: AgentMode,
-    gh_token: String,
-    google_token: String,
-    app_handle: tauri::AppHandle
-) -> Result<(), String> {
-    // 1. Resolve Recipe
-    let recipe_url = resolve_recipe(&recipe_id)?;
-
-    // 2. Github Client
-    let gh = GithubClient::new(gh_token.clone());
-
-    // Emit Booting
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::Booting,
-        logs: vec!["Provisioning GitHub resources...".to_string()],
-        pr_details: None,
-        plan: None,
-    });
-
-    // 3. Create Repo
-    let repo_full_name = gh.create_private_repo(&name)?;
-    let parts: Vec<&str> = repo_full_name.split('/').collect();
-    let owner = parts[0];
-    let repo = parts[1];
-
-    // 4. Create Codespace
-    let codespace_name = gh.create_codespace(owner, repo)?;
-    gh.wait_for_codespace(&codespace_name)?;
-
-    // 5. SSH Setup
-    let keys = generate_ephemeral_keypair()?;
-    let key_id = gh.add_deploy_key(owner, repo, &keys.public_key, "Command Center Ephemeral")?;
-
-    // Emit Generating
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::Generating,
-        logs: vec!["Connecting via SSH and running generator...".to_string()],
-        pr_details: None,
-        plan: None,
-    });
-
-    // 6. Execute Script
-    let command = format!(
-        "echo '{}' > AGENTS.md && curl -o run.sh {} && bash run.sh '{}'",
-        context, recipe_url, name
-    );
-    execute_ssh_command("mock_host", 22, "codespace", &keys.private_key, &keys.public_key, &command)?;
-
-    // 7. Cleanup
-    gh.remove_deploy_key(owner, repo, key_id)?;
-    gh.delete_codespace(&codespace_name)?;
-
-    // 8. Start Jules
-    // Emit Planning
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::Planning,
-        logs: vec!["Starting AI Session...".to_string()],
-        pr_details: None,
-        plan: None,
-    });
-
-    let jules = JulesClient::new(google_token);
-    let session_id = jules.start_session(
-        &format!("github.com/{}", repo_full_name),
-        "Review the generated code and make improvements.",
-        matches!(mode, AgentMode::Interactive)
-    )?;
-
-    // 9. Polling Loop
-    loop {
-        thread::sleep(Duration::from_secs(5));
-
-        match jules.poll_session(&session_id) {
-            Ok((status, pr, plan)) => {
-                let mut logs = vec![];
-                match status {
-                    JobStatus::Planning => logs.push("Jules is thinking...".to_string()),
-                    JobStatus::Working => logs.push("Jules is working on code...".to_string()),
-                    JobStatus::WaitingApproval => logs.push("Plan ready for review.".to_string()),
-                    JobStatus::PrReady => logs.push("Pull Request created.".to_string()),
-                    _ => {}
-                }
-
-                emit_update(&app_handle, JobUpdateEvent {
-                    id: job_id.clone(),
-                    status: status.clone(),
-                    logs,
-                    pr_details: pr,
-                    plan,
-                });
+/// The same steps for a repo an earlier, interrupted run already created.
+pub fn scaffold_steps_existing(owner: &str, repo: &str, name: &str, recipe_id: &str, context: &str, prompt: &str, session: &SessionSettings) -> Result<Pipeline, String> {
+    let mut steps = scaffold_steps(name, recipe_id, context, prompt, session)?;
+    steps[0] = Box::new(VerifyAccess { owner: owner.to_string(), repo: repo.to_string() });
+    Ok(steps)
+}
 
-                // Exit conditions
-                if matches!(status, JobStatus::PrReady | JobStatus::Merged) {
-                    break;
-                }
+pub fn run_scaffold_job(ctx: JobContext, name: &str, recipe_id: &str, context: &str, prompt: &str, session: &SessionSettings) -> Result<(), JobFailure> {
+    let steps = scaffold_steps(name, recipe_id, context, prompt, session).map_err(JobFailure::before_steps)?;
+    pipeline::run(ctx, steps, JobStatus::Booting)
+}
 
-                // If waiting for approval, we might want to break or continue polling depending on implementation.
-                // PRD says "poll every 5s", so we continue even if waiting, though practically we pause until action.
-                // We'll continue polling to see if state changes (e.g. from resume action).
-            }
-            Err(e) => {
-                println!("Polling error: {}", e);
-                // retry
-            }
-        }
+/// Creates the job and starts it in the background. Returns the job id.
+/// Must be called on a blocking thread.
+pub fn start_job(app: &tauri::AppHandle, name: String, recipe_id: String, context: String, mode: AgentMode, prompt: Option<String>, session: SessionSettings) -> Result<String, String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    if let Some(prompt) = &prompt {
+        prompts::validate(prompt)?;
     }
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    app.state::<crate::AppState>().jobs.insert(JobState::new(
+        job_id.clone(),
+        format!("user/{}", name), // approximation, replaced once the repo exists
+        JobStatus::Booting,
+        JobParams::Scaffold {
+            name: name.clone(),
+            recipe_id: recipe_id.clone(),
+            context: context.clone(),
+            mode: mode.clone(),
+            prompt: prompt.clone(),
+            session: session.clone(),
+        },
+    ));
+
+    let handle = app.clone();
+    let id = job_id.clone();
+    pipeline::spawn(app.clone(), job_id.clone(), move || {
+        let ctx = JobContext::new(handle, id, gh_token, jules_config, mode);
+        let prompt = prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+        run_scaffold_job(ctx, &name, &recipe_id, &context, prompt, &session)
+    });
+    Ok(job_id)
+}
 
-    Ok(())
+/// Resolves the job without creating it or the repo and reports what it would
+/// do. Must be called on a blocking thread.
+pub fn dry_run(app: &tauri::AppHandle, name: &str, recipe_id: &str, context: &str, mode: AgentMode, prompt: Option<&str>, session: &SessionSettings) -> Result<DryRunReport, String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty()).unwrap_or(DEFAULT_PROMPT);
+    prompts::validate(prompt)?;
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
+
+    let steps = scaffold_steps(name, recipe_id, context, prompt, session)?;
+    let ctx = JobContext::for_dry_run(app.clone(), gh_token, jules_config, mode);
+    Ok(dry_run::plan(ctx, steps))
 }
@@ -0,0 +1,400 @@
+use crate::poller::emit_update;
+use crate::types::{JobStatus, JobUpdateEvent};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Jules caps how many sessions can run at once and how many can be started per
+// day. Jobs take a slot right before their session starts and hold it until the
+// poll loop ends; anything over the limits waits in `Queued`, first come first served.
+
+const LIMITS_KEY: &str = "jules_limits";
+const STARTS_KEY: &str = "jules_session_starts";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaLimits {
+    pub max_concurrent: usize,
+    pub max_daily: usize, // Rolling 24 hours
+}
+
+impl Default for QuotaLimits {
+    fn default() -> Self {
+        QuotaLimits { max_concurrent: 3, max_daily: 15 }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaUsage {
+    pub limits: QuotaLimits,
+    pub active: usize,
+    pub started_today: usize,
+    pub queued: usize,
+    pub next_available: Option<String>, // RFC 3339. None when a slot is free now, or when only running jobs can free one
+}
+
+struct SchedulerState {
+    limits: QuotaLimits,
+    active: HashSet<String>,
+    starting: HashSet<String>, // Active but not yet started; they may still use a daily slot
+    waiting: VecDeque<String>,
+    starts: Vec<DateTime<Utc>>, // Session starts in the last 24 hours
+}
+
+impl SchedulerState {
+    fn prune(&mut self) {
+        let cutoff = Utc::now() - Duration::hours(24);
+        self.starts.retain(|t| *t > cutoff);
+    }
+
+    // Why a job can't start right now, if it can't
+    fn blocked_reason(&self) -> Option<String> {
+        let used = self.starts.len() + self.starting.len();
+        if used >= self.limits.max_daily {
+            Some(format!("daily limit reached ({}/{})", used, self.limits.max_daily))
+        } else if self.active.len() >= self.limits.max_concurrent {
+            Some(format!("{}/{} sessions running", self.active.len(), self.limits.max_concurrent))
+        } else {
+            None
+        }
+    }
+
+    // The oldest start drops out of the 24 hour window first
+    fn next_available(&self) -> Option<DateTime<Utc>> {
+        if self.starts.len() + self.starting.len() < self.limits.max_daily {
+            return None;
+        }
+        self.starts.iter().min().map(|t| *t + Duration::hours(24))
+    }
+}
+
+pub struct Scheduler {
+    state: Mutex<SchedulerState>,
+    slot_freed: Condvar,
+}
+
+/// A held Jules slot. Dropping it frees the slot for the next queued job.
+pub struct SlotGuard {
+    scheduler: Arc<Scheduler>,
+    job_id: String,
+}
+
+impl SlotGuard {
+    /// Counts the session against the daily limit, once Jules has accepted it.
+    pub fn started<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) {
+        let starts = self.scheduler.record_start(&self.job_id);
+        if let Ok(store) = app.store(APP_STORE) {
+            store.set(STARTS_KEY, serde_json::json!(starts));
+            if let Err(e) = store.save() {
+                println!("Failed to save Jules quota usage: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        state.active.remove(&self.job_id);
+        // A session that never started gives its daily slot back
+        state.starting.remove(&self.job_id);
+        drop(state);
+        self.scheduler.slot_freed.notify_all();
+    }
+}
+
+// A job's place in the queue. Dropping it, however the wait ends, leaves the queue.
+struct QueuePlace<'a> {
+    scheduler: &'a Scheduler,
+    job_id: &'a str,
+}
+
+impl Drop for QueuePlace<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.waiting.retain(|id| id != self.job_id);
+        drop(state);
+        self.scheduler.slot_freed.notify_all();
+    }
+}
+
+impl Scheduler {
+    fn new(limits: QuotaLimits, starts: Vec<DateTime<Utc>>) -> Self {
+        let mut state = SchedulerState {
+            limits,
+            active: HashSet::new(),
+            starting: HashSet::new(),
+            waiting: VecDeque::new(),
+            starts,
+        };
+        state.prune();
+        Scheduler { state: Mutex::new(state), slot_freed: Condvar::new() }
+    }
+
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let store = app.store(APP_STORE).ok();
+        let limits = store.as_ref()
+            .and_then(|s| s.get(LIMITS_KEY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        let starts = store.as_ref()
+            .and_then(|s| s.get(STARTS_KEY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        Scheduler::new(limits, starts)
+    }
+
+    pub fn usage(&self) -> QuotaUsage {
+        let mut state = self.state.lock().unwrap();
+        state.prune();
+        QuotaUsage {
+            limits: state.limits,
+            active: state.active.len(),
+            started_today: state.starts.len(),
+            queued: state.waiting.len(),
+            next_available: state.next_available().map(|t| t.to_rfc3339()),
+        }
+    }
+
+    pub fn set_limits<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, limits: QuotaLimits) -> Result<(), String> {
+        if limits.max_concurrent == 0 || limits.max_daily == 0 {
+            return Err("Limits must be at least 1".to_string());
+        }
+        self.state.lock().unwrap().limits = limits;
+        self.slot_freed.notify_all();
+
+        let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+        store.set(LIMITS_KEY, serde_json::json!(limits));
+        store.save().map_err(|e| e.to_string())
+    }
+
+    /// Counts a session the app didn't start (e.g. an imported one) against the
+    /// concurrency limit without waiting or using daily quota.
+    pub fn adopt(self: &Arc<Self>, job_id: &str) -> SlotGuard {
+        self.state.lock().unwrap().active.insert(job_id.to_string());
+        SlotGuard { scheduler: self.clone(), job_id: job_id.to_string() }
+    }
+
+    // Returns the starts to persist
+    fn record_start(&self, job_id: &str) -> Vec<DateTime<Utc>> {
+        let mut state = self.state.lock().unwrap();
+        if state.starting.remove(job_id) {
+            state.starts.push(Utc::now());
+        }
+        state.starts.clone()
+    }
+
+    // Blocks until the job is first in line and under both limits. `on_blocked`
+    // gets each new reason the job is waiting, without the lock held.
+    fn wait_for_slot(self: &Arc<Self>, job_id: &str, cancel: &AtomicBool, mut on_blocked: impl FnMut(String)) -> Option<SlotGuard> {
+        self.state.lock().unwrap().waiting.push_back(job_id.to_string());
+        let _place = QueuePlace { scheduler: self, job_id };
+        let mut last_log: Option<String> = None;
+
+        loop {
+            let mut state = self.state.lock().unwrap();
+            if cancel.load(Ordering::SeqCst) {
+                return None;
+            }
+
+            state.prune();
+            let position = state.waiting.iter().position(|id| id == job_id).unwrap_or(0);
+            let blocked = match state.blocked_reason() {
+                Some(reason) => Some(reason),
+                None if position > 0 => Some(format!("{} job(s) ahead in the queue", position)),
+                None => None,
+            };
+
+            let Some(reason) = blocked else {
+                state.waiting.retain(|id| id != job_id);
+                state.active.insert(job_id.to_string());
+                state.starting.insert(job_id.to_string());
+                return Some(SlotGuard { scheduler: self.clone(), job_id: job_id.to_string() });
+            };
+
+            let mut log = format!("Queued for Jules: {}.", reason);
+            if let Some(at) = state.next_available() {
+                log.push_str(&format!(" Next slot expected at {}.", at.to_rfc3339()));
+            }
+            if last_log.as_ref() != Some(&log) {
+                drop(state);
+                on_blocked(log.clone());
+                last_log = Some(log);
+                // Look again before waiting, in case a slot freed up meanwhile
+                continue;
+            }
+
+            // Daily slots free up by time passing, not by a notify, so wake up periodically
+            let _ = self.slot_freed.wait_timeout(state, std::time::Duration::from_secs(5)).unwrap();
+        }
+    }
+}
+
+/// Blocks until the job may start a Jules session, emitting `Queued` while it waits.
+/// Returns None if the job is cancelled in the meantime. The slot counts against
+/// the daily limit once `SlotGuard::started` is called.
+pub fn acquire(app: &tauri::AppHandle, job_id: &str, cancel: &AtomicBool) -> Option<SlotGuard> {
+    let scheduler = app.state::<crate::AppState>().scheduler.clone();
+    let slot = scheduler.wait_for_slot(job_id, cancel, |log| {
+        emit_update(app, JobUpdateEvent::new(job_id, JobStatus::Queued, vec![log]));
+    });
+    // The next job in line may be able to go too
+    scheduler.slot_freed.notify_all();
+    slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration as StdDuration;
+
+    fn scheduler_with(max_concurrent: usize, max_daily: usize, starts: Vec<DateTime<Utc>>) -> Arc<Scheduler> {
+        Arc::new(Scheduler::new(QuotaLimits { max_concurrent, max_daily }, starts))
+    }
+
+    fn take(scheduler: &Arc<Scheduler>, job_id: &str) -> SlotGuard {
+        scheduler.wait_for_slot(job_id, &AtomicBool::new(false), |_| {}).unwrap()
+    }
+
+    // Starts a job waiting in the background and returns once it is queued
+    fn queue(scheduler: &Arc<Scheduler>, job_id: &str, done: &mpsc::Sender<(String, SlotGuard)>) -> Arc<AtomicBool> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let queued = scheduler.usage().queued;
+        let (s, id, c, done) = (scheduler.clone(), job_id.to_string(), cancel.clone(), done.clone());
+        thread::spawn(move || {
+            if let Some(slot) = s.wait_for_slot(&id, &c, |_| {}) {
+                let _ = done.send((id, slot));
+            }
+        });
+        while scheduler.usage().queued == queued {
+            thread::sleep(StdDuration::from_millis(5));
+        }
+        cancel
+    }
+
+    fn next(done: &mpsc::Receiver<(String, SlotGuard)>) -> (String, SlotGuard) {
+        done.recv_timeout(StdDuration::from_secs(2)).expect("no job got a slot")
+    }
+
+    fn nothing_more(done: &mpsc::Receiver<(String, SlotGuard)>) {
+        assert!(done.recv_timeout(StdDuration::from_millis(100)).is_err(), "a job started over the limit");
+    }
+
+    #[test]
+    fn queued_jobs_start_in_order() {
+        let scheduler = scheduler_with(1, 10, vec![]);
+        let first = take(&scheduler, "a");
+        let (tx, rx) = mpsc::channel();
+        queue(&scheduler, "b", &tx);
+        queue(&scheduler, "c", &tx);
+        nothing_more(&rx);
+
+        drop(first);
+        let (id, b) = next(&rx);
+        assert_eq!(id, "b");
+        nothing_more(&rx);
+        drop(b);
+        assert_eq!(next(&rx).0, "c");
+        assert_eq!(scheduler.usage().queued, 0);
+    }
+
+    #[test]
+    fn the_concurrent_limit_holds_until_a_slot_is_freed() {
+        let scheduler = scheduler_with(2, 10, vec![]);
+        let a = take(&scheduler, "a");
+        let _b = take(&scheduler, "b");
+        let (tx, rx) = mpsc::channel();
+        queue(&scheduler, "c", &tx);
+        nothing_more(&rx);
+        let usage = scheduler.usage();
+        assert_eq!((usage.active, usage.queued), (2, 1));
+
+        drop(a);
+        assert_eq!(next(&rx).0, "c");
+    }
+
+    #[test]
+    fn a_cancelled_job_leaves_the_queue() {
+        let scheduler = scheduler_with(1, 10, vec![]);
+        let first = take(&scheduler, "a");
+        let (tx, rx) = mpsc::channel();
+        let cancel_b = queue(&scheduler, "b", &tx);
+        queue(&scheduler, "c", &tx);
+
+        cancel_b.store(true, Ordering::SeqCst);
+        scheduler.slot_freed.notify_all();
+        while scheduler.usage().queued > 1 {
+            thread::sleep(StdDuration::from_millis(5));
+        }
+        drop(first);
+        assert_eq!(next(&rx).0, "c");
+    }
+
+    #[test]
+    fn a_waiter_that_dies_does_not_block_the_queue() {
+        let scheduler = scheduler_with(1, 10, vec![]);
+        let first = take(&scheduler, "a");
+        let s = scheduler.clone();
+        let dead = thread::spawn(move || {
+            s.wait_for_slot("b", &AtomicBool::new(false), |_| panic!("job thread died"));
+        });
+        assert!(dead.join().is_err());
+        assert_eq!(scheduler.usage().queued, 0);
+
+        let (tx, rx) = mpsc::channel();
+        queue(&scheduler, "c", &tx);
+        drop(first);
+        assert_eq!(next(&rx).0, "c");
+    }
+
+    #[test]
+    fn only_started_sessions_use_daily_quota() {
+        let scheduler = scheduler_with(5, 2, vec![]);
+        drop(take(&scheduler, "rejected")); // Jules answered 429, say
+        assert_eq!(scheduler.usage().started_today, 0);
+
+        let a = take(&scheduler, "a");
+        scheduler.record_start("a");
+        scheduler.record_start("a"); // Counted once
+        let b = take(&scheduler, "b");
+        assert_eq!(scheduler.usage().started_today, 1);
+
+        // b hasn't started yet but holds the last daily slot
+        let (tx, rx) = mpsc::channel();
+        queue(&scheduler, "c", &tx);
+        nothing_more(&rx);
+        drop(b);
+        let (_, c) = next(&rx);
+        drop((a, c));
+    }
+
+    #[test]
+    fn daily_quota_frees_up_after_24_hours() {
+        let now = Utc::now();
+        let starts = vec![now - Duration::hours(25), now - Duration::hours(23), now - Duration::hours(1)];
+        let scheduler = scheduler_with(5, 2, starts);
+        let usage = scheduler.usage();
+        assert_eq!(usage.started_today, 2);
+        let next = DateTime::parse_from_rfc3339(&usage.next_available.unwrap()).unwrap();
+        assert_eq!(next, now + Duration::hours(1));
+
+        let (tx, rx) = mpsc::channel();
+        let cancel = queue(&scheduler, "a", &tx);
+        nothing_more(&rx);
+        cancel.store(true, Ordering::SeqCst);
+
+        // With one more slot the 23 hour old start leaves room
+        let roomier = scheduler_with(5, 3, vec![now - Duration::hours(23), now - Duration::hours(1)]);
+        assert!(roomier.usage().next_available.is_none());
+        let _a = take(&roomier, "a");
+        assert!(roomier.usage().next_available.is_some());
+    }
+}
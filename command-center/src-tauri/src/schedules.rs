@@ -0,0 +1,472 @@
+use crate::batch::{self, BatchTarget};
+use crate::types::{AgentMode, SessionSettings};
+use crate::uplink_engine;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Recurring jobs. A schedule is a cron expression, evaluated in local time,
+// plus what to start when it fires: an uplink or a batch uplink. Schedules
+// only fire while the app runs; runs that came due while it was closed are
+// handled by the schedule's MissedRuns policy at the next tick.
+
+const SCHEDULES_KEY: &str = "schedules";
+const TICK: std::time::Duration = std::time::Duration::from_secs(30);
+const MAX_RUNS: usize = 20; // Run history kept per schedule
+
+// A run this far past its time was missed, not just picked up by the next tick
+const LATE_AFTER_SECS: i64 = 120;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum ScheduledTask {
+    Uplink {
+        repo_url: String,
+        context: String,
+        mode: AgentMode,
+        #[serde(default)]
+        prompt: Option<String>,
+    },
+    Batch {
+        target: BatchTarget,
+        context: String,
+        mode: AgentMode,
+        #[serde(default)]
+        prompt: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRuns {
+    #[default]
+    Skip,
+    CatchUp, // One run, however many were missed
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRun {
+    pub scheduled_for: DateTime<Utc>,
+    pub fired_at: Option<DateTime<Utc>>, // None when a missed run was skipped
+    pub job_id: Option<String>,
+    pub batch_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub cron: String,
+    pub task: ScheduledTask,
+    #[serde(default)]
+    pub missed_runs: MissedRuns,
+    #[serde(default)]
+    pub paused: bool,
+    pub next_run: Option<DateTime<Utc>>, // None while paused
+    #[serde(default)]
+    pub runs: Vec<ScheduledRun>, // Newest last
+}
+
+/// A parsed five-field cron expression: minute hour day-of-month month day-of-week.
+/// Fields take `*`, numbers, ranges, lists and steps; months and weekdays also
+/// take three-letter names. `@hourly`, `@daily`, `@weekly` and `@monthly` work too.
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+// Bitmask of the values `spec` selects. Names map to min + their index.
+fn parse_field(spec: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        match names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            Some(i) => Ok(min + i as u32),
+            None => s.parse().map_err(|_| format!("Invalid value \"{}\"", s)),
+        }
+    };
+
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|s| *s > 0).ok_or(format!("Invalid step in \"{}\"", part))?),
+            None => (part, 1),
+        };
+        let (lo, hi) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((a, b)) => (value(a)?, value(b)?),
+            // "5/15" means from 5 to the end in steps of 15
+            None if step > 1 => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(format!("\"{}\" is outside {}-{}", part, min, max));
+        }
+        for v in (lo..=hi).step_by(step) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Cron, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err("Expected five fields: minute hour day-of-month month day-of-week".to_string());
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, &WEEKDAY_NAMES)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1; // 7 is Sunday too
+        }
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTH_NAMES)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    // As in cron: when both day fields are restricted, either one matching is enough
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// The first matching minute strictly after `after`. None if there is none
+    /// in the next five years (e.g. February 30th).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_timezone(&Local).naive_local();
+        let mut t: NaiveDateTime = start.date().and_hms_opt(start.hour(), start.minute(), 0)? + Duration::minutes(1);
+        let limit = t + Duration::days(5 * 366);
+
+        while t < limit {
+            if self.months & (1 << t.month()) == 0 {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                // None inside a DST gap; that minute doesn't exist locally
+                if let Some(local) = Local.from_local_datetime(&t).earliest() {
+                    return Some(local.with_timezone(&Utc));
+                }
+                t += Duration::minutes(1);
+            }
+        }
+        None
+    }
+}
+
+pub struct Schedules {
+    state: Mutex<Vec<Schedule>>,
+}
+
+impl Schedules {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let schedules = app.store(APP_STORE).ok()
+            .and_then(|store| store.get(SCHEDULES_KEY))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+        Schedules { state: Mutex::new(schedules) }
+    }
+
+    fn save<R: tauri::Runtime>(app: &tauri::AppHandle<R>, schedules: &[Schedule]) -> Result<(), String> {
+        let store = app.store(APP_STORE).map_err(|e| e.to_string())?;
+        store.set(SCHEDULES_KEY, serde_json::json!(schedules));
+        store.save().map_err(|e| e.to_string())
+    }
+
+    pub fn list(&self) -> Vec<Schedule> {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn create<R: tauri::Runtime>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        name: String,
+        cron: String,
+        task: ScheduledTask,
+        missed_runs: MissedRuns,
+    ) -> Result<Schedule, String> {
+        let next_run = Cron::parse(&cron)?.next_after(Utc::now())
+            .ok_or("The schedule never fires")?;
+        match &task {
+            ScheduledTask::Uplink { repo_url, prompt, .. } => {
+                uplink_engine::parse_repo_url(repo_url)?;
+                crate::prompts::validate(prompt.as_deref().unwrap_or(""))?;
+            }
+            ScheduledTask::Batch { prompt, .. } => crate::prompts::validate(prompt)?,
+        }
+
+        let schedule = Schedule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            cron,
+            task,
+            missed_runs,
+            paused: false,
+            next_run: Some(next_run),
+            runs: vec![],
+        };
+        let mut schedules = self.state.lock().unwrap();
+        schedules.push(schedule.clone());
+        Self::save(app, &schedules)?;
+        Ok(schedule)
+    }
+
+    /// Resuming starts counting from now: runs due while paused are not missed runs.
+    pub fn set_paused<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, id: &str, paused: bool) -> Result<Schedule, String> {
+        let mut schedules = self.state.lock().unwrap();
+        let schedule = schedules.iter_mut().find(|s| s.id == id).ok_or("Schedule not found")?;
+        schedule.paused = paused;
+        schedule.next_run = match paused {
+            true => None,
+            false => Cron::parse(&schedule.cron)?.next_after(Utc::now()),
+        };
+        let schedule = schedule.clone();
+        Self::save(app, &schedules)?;
+        Ok(schedule)
+    }
+
+    /// Jobs already started by the schedule keep running.
+    pub fn delete<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, id: &str) -> Result<(), String> {
+        let mut schedules = self.state.lock().unwrap();
+        let before = schedules.len();
+        schedules.retain(|s| s.id != id);
+        if schedules.len() == before {
+            return Err("Schedule not found".to_string());
+        }
+        Self::save(app, &schedules)
+    }
+}
+
+/// Checks for due schedules every TICK for as long as the app runs.
+pub fn start_ticker(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        tick(&app);
+        std::thread::sleep(TICK);
+    });
+}
+
+fn tick(app: &tauri::AppHandle) {
+    let state = app.state::<crate::AppState>();
+    let now = Utc::now();
+
+    // Advance every due schedule first, so a slow start can't make it fire twice
+    let mut due = vec![];
+    {
+        let mut schedules = state.schedules.state.lock().unwrap();
+        for schedule in schedules.iter_mut().filter(|s| !s.paused) {
+            let Some(scheduled_for) = schedule.next_run.filter(|t| *t <= now) else { continue };
+            let missed = (now - scheduled_for).num_seconds() > LATE_AFTER_SECS;
+            schedule.next_run = Cron::parse(&schedule.cron).ok().and_then(|cron| cron.next_after(now));
+            due.push((schedule.id.clone(), schedule.task.clone(), scheduled_for, missed && schedule.missed_runs == MissedRuns::Skip));
+        }
+    }
+    for (id, task, scheduled_for, skip) in due {
+        let mut run = ScheduledRun { scheduled_for, fired_at: None, job_id: None, batch_id: None, error: None };
+        if skip {
+            println!("Schedule {} missed its run at {}, skipping", id, scheduled_for.to_rfc3339());
+            record_run(app, &id, run);
+            continue;
+        }
+        // Starting jobs makes HTTP calls, so it runs off the ticker thread and
+        // other schedules due now don't wait for it
+        run.fired_at = Some(Utc::now());
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = fire(&app, &id, task, &mut run) {
+                println!("Schedule {} failed to start: {}", id, e);
+                run.error = Some(e);
+            }
+            record_run(&app, &id, run);
+        });
+    }
+}
+
+fn record_run(app: &tauri::AppHandle, schedule_id: &str, run: ScheduledRun) {
+    let state = app.state::<crate::AppState>();
+    let mut schedules = state.schedules.state.lock().unwrap();
+    // Deleted while its jobs were starting
+    let Some(schedule) = schedules.iter_mut().find(|s| s.id == schedule_id) else { return };
+    schedule.runs.push(run);
+    if schedule.runs.len() > MAX_RUNS {
+        schedule.runs.remove(0);
+    }
+    if let Err(e) = app.emit("SCHEDULE_UPDATE", schedule.clone()) {
+        println!("Failed to emit event: {}", e);
+    }
+    if let Err(e) = Schedules::save(app, &schedules) {
+        println!("Failed to save schedules: {}", e);
+    }
+}
+
+// Starts the task and tags the jobs it created with the schedule
+fn fire(app: &tauri::AppHandle, schedule_id: &str, task: ScheduledTask, run: &mut ScheduledRun) -> Result<(), String> {
+    let job_ids = match task {
+        ScheduledTask::Uplink { repo_url, context, mode, prompt } => {
+            let job_id = uplink_engine::start_job(app, repo_url, context, mode, prompt, SessionSettings::default())?;
+            run.job_id = Some(job_id.clone());
+            vec![job_id]
+        }
+        ScheduledTask::Batch { target, context, mode, prompt } => {
+            let batch = batch::start(app, target, context, prompt, mode)?;
+            run.batch_id = Some(batch.id);
+            batch.job_ids
+        }
+    };
+    let jobs = &app.state::<crate::AppState>().jobs;
+    for job_id in job_ids {
+        jobs.update(&job_id, |job| job.schedule_id = Some(schedule_id.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(values: &[u32]) -> u64 {
+        values.iter().fold(0, |bits, v| bits | 1 << v)
+    }
+
+    // Local times, since that's what schedules are written in
+    fn local(text: &str) -> DateTime<Utc> {
+        let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap().with_timezone(&Utc)
+    }
+
+    // spec, min, max, names, selected values
+    type FieldCase = (&'static str, u32, u32, &'static [&'static str], &'static [u32]);
+
+    #[test]
+    fn fields_parse_to_the_values_they_select() {
+        let cases: [FieldCase; 10] = [
+            ("*", 0, 5, &[], &[0, 1, 2, 3, 4, 5]),
+            ("*/15", 0, 59, &[], &[0, 15, 30, 45]),
+            ("5/20", 0, 59, &[], &[5, 25, 45]),
+            ("1,3-5", 0, 59, &[], &[1, 3, 4, 5]),
+            ("10-20/5", 0, 59, &[], &[10, 15, 20]),
+            ("7", 0, 23, &[], &[7]),
+            ("jan,Dec", 1, 12, &MONTH_NAMES, &[1, 12]),
+            ("MAR-MAY", 1, 12, &MONTH_NAMES, &[3, 4, 5]),
+            ("MON-FRI", 0, 7, &WEEKDAY_NAMES, &[1, 2, 3, 4, 5]),
+            ("sun,6", 0, 7, &WEEKDAY_NAMES, &[0, 6]),
+        ];
+        for (spec, min, max, names, expected) in cases {
+            assert_eq!(parse_field(spec, min, max, names), Ok(bits(expected)), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn shortcuts_and_sunday_as_7() {
+        let daily = Cron::parse("@daily").unwrap();
+        assert_eq!((daily.minutes, daily.hours), (bits(&[0]), bits(&[0])));
+        assert!(daily.any_day && daily.any_weekday);
+
+        let weekly = Cron::parse(" @weekly ").unwrap();
+        assert_eq!(weekly.weekdays, bits(&[0]));
+
+        let sunday = Cron::parse("0 0 * * 7").unwrap();
+        assert_eq!(sunday.weekdays, bits(&[0, 7]));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        let cases = [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "@yearly",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 0 *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "*/x * * * *",
+            "5-1 * * * *",
+            "1- * * * *",
+            "abc * * * *",
+            "* * * FOO *",
+            "* * * * MONDAY",
+            "1,,2 * * * *",
+        ];
+        for expr in cases {
+            assert!(Cron::parse(expr).is_err(), "{:?} was accepted", expr);
+        }
+    }
+
+    #[test]
+    fn next_run_crosses_hour_day_month_and_year_boundaries() {
+        let cases = [
+            ("*/15 * * * *", "2026-06-10 08:07", "2026-06-10 08:15"),
+            ("*/15 * * * *", "2026-06-10 08:45", "2026-06-10 09:00"),
+            ("30 9 * * *", "2026-06-10 09:30", "2026-06-11 09:30"),
+            ("@hourly", "2026-06-30 23:59", "2026-07-01 00:00"),
+            ("0 0 1 * *", "2026-01-31 12:00", "2026-02-01 00:00"),
+            ("0 12 31 * *", "2026-04-15 00:00", "2026-05-31 12:00"),
+            ("@daily", "2026-12-31 23:59", "2027-01-01 00:00"),
+            ("0 6 * JAN *", "2026-02-01 00:00", "2027-01-01 06:00"),
+            ("0 0 29 2 *", "2026-03-01 00:00", "2028-02-29 00:00"),
+            ("0 9 * * MON-FRI", "2027-01-01 10:00", "2027-01-04 09:00"),
+            ("@weekly", "2026-12-30 00:00", "2027-01-03 00:00"),
+            // Either day field is enough when both are set: the 13th, or any Friday
+            ("0 0 13 * FRI", "2026-12-05 00:00", "2026-12-11 00:00"),
+            ("0 0 13 * FRI", "2026-12-11 00:00", "2026-12-13 00:00"),
+        ];
+        for (expr, after, expected) in cases {
+            let next = Cron::parse(expr).unwrap().next_after(local(after));
+            assert_eq!(next, Some(local(expected)), "{} after {}", expr, after);
+        }
+    }
+
+    #[test]
+    fn next_run_ignores_seconds_and_is_strictly_later() {
+        let cron = Cron::parse("* * * * *").unwrap();
+        let after = local("2026-06-10 08:07") + Duration::seconds(59);
+        assert_eq!(cron.next_after(after), Some(local("2026-06-10 08:08")));
+        assert_eq!(cron.next_after(local("2026-06-10 08:08")), Some(local("2026-06-10 08:09")));
+    }
+
+    #[test]
+    fn a_date_that_never_comes_has_no_next_run() {
+        assert_eq!(Cron::parse("0 0 30 2 *").unwrap().next_after(local("2026-01-01 00:00")), None);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::automerge;
+use crate::dry_run;
+use crate::jules::{self, SessionOptions};
+use crate::pipeline::{JobContext, Step};
+use crate::poller;
+use crate::prompts;
+use crate::scheduler;
+use crate::ssh_utils::{execute_ssh_command, generate_ephemeral_keypair};
+use crate::guards::{CodespaceGuard, DeployKeyGuard};
+use crate::sweeper;
+use crate::types::{AgentMode, JobStatus, SessionSettings};
+use tauri::Manager;
+
+// The building blocks job types are composed from. Each step only relies on
+// the context fields filled in by the steps before it.
+
+/// Scaffold: creates a private repo and makes it the job's repo.
+pub struct CreateRepo {
+    pub name: String,
+}
+
+impl Step for CreateRepo {
+    fn name(&self) -> &'static str {
+        "create_repo"
+    }
+
+    fn describe(&self) -> String {
+        "Provisioning GitHub resources...".to_string()
+    }
+
+    fn status(&self) -> Option<JobStatus> {
+        Some(JobStatus::Booting)
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        let full_name = ctx.gh.create_private_repo(&self.name)?;
+        sweeper::record_created_repo(&ctx.app, &full_name);
+        ctx.created_repo = Some(full_name.clone());
+        ctx.app.state::<crate::AppState>()
+            .update_job(&ctx.job_id, |job| job.github_repo = full_name.clone());
+        let (owner, repo) = full_name.split_once('/').ok_or("Unexpected repo name")?;
+        ctx.owner = owner.to_string();
+        ctx.repo = repo.to_string();
+        Ok(())
+    }
+
+    // Later steps see created_repo set and know the repo doesn't exist yet
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        ctx.owner = ctx.gh.get_authenticated_user()?;
+        ctx.repo = self.name.clone();
+        let full_name = format!("{}/{}", ctx.owner, ctx.repo);
+        ctx.created_repo = Some(full_name.clone());
+        Ok(vec![format!("Create private repository {}", full_name)])
+    }
+}
+
+/// Uplink: points the job at an existing repo the user can push to.
+pub struct VerifyAccess {
+    pub owner: String,
+    pub repo: String,
+}
+
+impl Step for VerifyAccess {
+    fn name(&self) -> &'static str {
+        "verify_access"
+    }
+
+    fn describe(&self) -> String {
+        "Verifying repository access...".to_string()
+    }
+
+    fn status(&self) -> Option<JobStatus> {
+        Some(JobStatus::Booting)
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        ctx.owner = self.owner.clone();
+        ctx.repo = self.repo.clone();
+        if !ctx.gh.check_repo_access(&ctx.owner, &ctx.repo)? {
+            return Err("No write access to repo".to_string());
+        }
+        Ok(())
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        self.run(ctx)?;
+        Ok(vec![format!("Use existing repository {}/{}", ctx.owner, ctx.repo)])
+    }
+}
+
+pub struct CreateCodespace;
+
+impl Step for CreateCodespace {
+    fn name(&self) -> &'static str {
+        "create_codespace"
+    }
+
+    fn describe(&self) -> String {
+        "Creating codespace...".to_string()
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        let name = ctx.gh.create_codespace(&ctx.owner, &ctx.repo, &sweeper::codespace_display_name(&ctx.job_id))?;
+        let guard = CodespaceGuard::new(&ctx.gh, &ctx.app, &ctx.job_id, name);
+        let ready = ctx.gh.wait_for_codespace(guard.name());
+        ctx.codespace = Some(guard);
+        ready
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        Ok(vec![format!(
+            "Create codespace \"{}\" on {}/{}",
+            sweeper::codespace_display_name(&ctx.job_id), ctx.owner, ctx.repo
+        )])
+    }
+
+    fn compensate(&self, ctx: &mut JobContext) -> Vec<String> {
+        ctx.codespace.take().map(|guard| guard.release()).into_iter().collect()
+    }
+}
+
+pub struct AddDeployKey;
+
+impl Step for AddDeployKey {
+    fn name(&self) -> &'static str {
+        "add_deploy_key"
+    }
+
+    fn describe(&self) -> String {
+        "Adding ephemeral deploy key...".to_string()
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        let keys = generate_ephemeral_keypair()?;
+        let title = sweeper::deploy_key_title(&ctx.job_id);
+        let key_id = ctx.gh.add_deploy_key(&ctx.owner, &ctx.repo, &keys.public_key, &title)?;
+        ctx.deploy_key = Some(DeployKeyGuard::new(&ctx.gh, &ctx.app, &ctx.job_id, &ctx.owner, &ctx.repo, key_id));
+        ctx.keys = Some(keys);
+        Ok(())
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        Ok(vec![format!(
+            "Generate an ephemeral SSH key and add it to {}/{} as deploy key \"{}\"",
+            ctx.owner, ctx.repo, sweeper::deploy_key_title(&ctx.job_id)
+        )])
+    }
+
+    fn compensate(&self, ctx: &mut JobContext) -> Vec<String> {
+        ctx.keys = None;
+        ctx.deploy_key.take().map(|guard| guard.release()).into_iter().collect()
+    }
+}
+
+/// Runs a recipe script in the codespace over SSH.
+pub struct RunGenerator {
+    pub recipe_url: String,
+    pub name: String,
+    pub context: String,
+}
+
+impl RunGenerator {
+    fn command(&self) -> String {
+        format!(
+            "echo '{}' > AGENTS.md && curl -o run.sh {} && bash run.sh '{}'",
+            self.context, self.recipe_url, self.name
+        )
+    }
+}
+
+impl Step for RunGenerator {
+    fn name(&self) -> &'static str {
+        "run_generator"
+    }
+
+    fn describe(&self) -> String {
+        "Connecting via SSH and running generator...".to_string()
+    }
+
+    fn status(&self) -> Option<JobStatus> {
+        Some(JobStatus::Generating)
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        let keys = ctx.keys.as_ref().ok_or("No SSH keys for the codespace")?;
+        execute_ssh_command("mock_host", 22, "codespace", &keys.private_key, &keys.public_key, &self.command())?;
+        Ok(())
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        let current = match ctx.created_repo {
+            Some(_) => None,
+            None => ctx.gh.get_file_content(&ctx.owner, &ctx.repo, "AGENTS.md")?,
+        };
+        let mut details = vec![
+            format!("Recipe: {}", self.recipe_url),
+            format!("Run over SSH in the codespace: {}", self.command()),
+            "AGENTS.md:".to_string(),
+        ];
+        details.extend(dry_run::line_diff(current.as_deref().unwrap_or(""), &self.context));
+        Ok(details)
+    }
+}
+
+/// Tears down the codespace and deploy key once they are no longer needed.
+/// A failed teardown is a warning, not a job failure: the work is already done.
+pub struct ReleaseWorkspace;
+
+impl Step for ReleaseWorkspace {
+    fn name(&self) -> &'static str {
+        "release_workspace"
+    }
+
+    fn describe(&self) -> String {
+        "Cleaning up workspace...".to_string()
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        ctx.keys = None;
+        if let Some(guard) = ctx.deploy_key.take() {
+            guard.release();
+        }
+        if let Some(guard) = ctx.codespace.take() {
+            guard.release();
+        }
+        Ok(())
+    }
+
+    fn plan(&self, _ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        Ok(vec!["Delete the codespace and deploy key".to_string()])
+    }
+}
+
+/// Commits AGENTS.md with the job's context.
+pub struct UploadContext {
+    pub context: String,
+}
+
+impl Step for UploadContext {
+    fn name(&self) -> &'static str {
+        "upload_context"
+    }
+
+    fn describe(&self) -> String {
+        "Syncing AGENTS.md...".to_string()
+    }
+
+    fn status(&self) -> Option<JobStatus> {
+        Some(JobStatus::UploadingContext)
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        ctx.gh.update_file(&ctx.owner, &ctx.repo, "AGENTS.md", &self.context, "Update AGENTS.md via Command Center")
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        let current = ctx.gh.get_file_content(&ctx.owner, &ctx.repo, "AGENTS.md")?;
+        if current.as_deref() == Some(self.context.as_str()) {
+            return Ok(vec!["AGENTS.md already has this content".to_string()]);
+        }
+        let mut details = vec![match current {
+            Some(_) => format!("Commit changes to AGENTS.md in {}/{}:", ctx.owner, ctx.repo),
+            None => format!("Commit a new AGENTS.md to {}/{}:", ctx.owner, ctx.repo),
+        }];
+        details.extend(dry_run::line_diff(current.as_deref().unwrap_or(""), &self.context));
+        Ok(details)
+    }
+}
+
+/// Waits for a Jules slot, then starts a session on the job's repo.
+pub struct StartSession {
+    pub prompt: String, // Template, see prompts::VARIABLES
+    pub title: String,  // Unless the user picked one
+    pub recipe: Option<String>,
+    pub session: SessionSettings,
+}
+
+impl StartSession {
+    // The expanded prompt and the options to start the session with
+    fn request(&self, ctx: &JobContext, default_branch: String) -> (String, SessionOptions) {
+        let prompt = prompts::expand(&self.prompt, &[
+            ("repo", &format!("{}/{}", ctx.owner, ctx.repo)),
+            ("recipe", self.recipe.as_deref().unwrap_or("")),
+            ("default_branch", &default_branch),
+        ]);
+        let chosen = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+        let options = SessionOptions {
+            title: Some(chosen(&self.session.title).unwrap_or_else(|| self.title.clone())),
+            starting_branch: Some(chosen(&self.session.starting_branch).unwrap_or(default_branch)),
+            automation_mode: self.session.automation_mode.unwrap_or(SessionOptions::default().automation_mode),
+            require_plan_approval: matches!(ctx.mode, AgentMode::Interactive),
+        };
+        (prompt, options)
+    }
+}
+
+impl Step for StartSession {
+    fn name(&self) -> &'static str {
+        "start_session"
+    }
+
+    fn describe(&self) -> String {
+        "Starting AI Session...".to_string()
+    }
+
+    fn status(&self) -> Option<JobStatus> {
+        Some(JobStatus::Planning)
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        // The job is done with GitHub, so the next batch child can start on it
+        ctx.github_permit = None;
+        // Held until the pipeline ends; waits in Queued if the limits are hit
+        ctx.slot = Some(scheduler::acquire(&ctx.app, &ctx.job_id, &ctx.cancel).ok_or("Cancelled while queued")?);
+
+        // For a new repo this only succeeds if the Jules app covers all of the owner's repos
+        let source = ctx.jules.require_source(&ctx.owner, &ctx.repo)?;
+        let default_branch = ctx.gh.get_default_branch(&ctx.owner, &ctx.repo)?;
+        let (prompt, options) = self.request(ctx, default_branch);
+        let session_id = ctx.jules.start_session(&source, &prompt, &options)?;
+        // Only a session Jules accepted uses up daily quota
+        if let Some(slot) = &ctx.slot {
+            slot.started(&ctx.app);
+        }
+        ctx.app.state::<crate::AppState>()
+            .update_job(&ctx.job_id, |job| job.jules_session_id = Some(session_id.clone()));
+        ctx.session_id = Some(session_id);
+        Ok(())
+    }
+
+    fn plan(&self, ctx: &mut JobContext) -> Result<Vec<String>, String> {
+        let mut details = vec![];
+        // A repo the job would create doesn't exist yet, so there is nothing to look up
+        let (source, default_branch) = match ctx.created_repo {
+            Some(_) => {
+                details.push("The Jules source and default branch are checked once the repository exists".to_string());
+                (format!("sources/github/{}/{}", ctx.owner, ctx.repo), "main".to_string())
+            }
+            None => (ctx.jules.require_source(&ctx.owner, &ctx.repo)?, ctx.gh.get_default_branch(&ctx.owner, &ctx.repo)?),
+        };
+        let (prompt, options) = self.request(ctx, default_branch);
+        let body = jules::session_request_body(&source, &prompt, &options);
+        details.push("Wait for a Jules slot, then start a session with:".to_string());
+        details.push(serde_json::to_string_pretty(&body).map_err(|e| e.to_string())?);
+        Ok(details)
+    }
+
+    // Covers a cancel that landed while the session was being created, which
+    // cancel_job couldn't see. A session that failed or ran out of polling time
+    // is kept, and the failure records its id.
+    fn compensate(&self, ctx: &mut JobContext) -> Vec<String> {
+        ctx.slot = None;
+        let Some(session_id) = ctx.session_id.take() else { return vec![] };
+        if !ctx.cancelled() {
+            return vec![format!("Jules session {} was kept.", session_id)];
+        }
+        match ctx.jules.cancel_session(&session_id) {
+            Ok(()) => vec!["Cancelled Jules session.".to_string()],
+            Err(e) => vec![format!("Failed to cancel Jules session: {}", e)],
+        }
+    }
+}
+
+/// Follows the session until Jules is done with it.
+pub struct PollSession;
+
+impl Step for PollSession {
+    fn name(&self) -> &'static str {
+        "poll_session"
+    }
+
+    fn describe(&self) -> String {
+        "Waiting for Jules...".to_string()
+    }
+
+    fn run(&self, ctx: &mut JobContext) -> Result<(), String> {
+        let session_id = ctx.session_id.clone().ok_or("No Jules session to poll")?;
+        let result = poller::poll_until_done(&ctx.app, &ctx.jules, &ctx.job_id, &session_id);
+        ctx.slot = None;
+        if result.is_ok() {
+            automerge::spawn(&ctx.app, ctx.gh.clone(), &ctx.job_id);
+        }
+        result
+    }
+}
@@ -0,0 +1,358 @@
+use crate::github::{CodespaceInfo, DeployKeyInfo, GithubClient};
+use crate::types::{JobParams, JobState, JobStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use tauri::Manager;
+use tauri_plugin_store::StoreExt;
+use crate::APP_STORE;
+
+// Finds codespaces and deploy keys left behind by scaffold jobs that died
+// before their cleanup step. Resources are tagged with the job id when created
+// so they can be matched back to the job that owns them.
+
+const CODESPACE_TAG_PREFIX: &str = "cc-";
+const DEPLOY_KEY_TITLE: &str = "Command Center Ephemeral";
+const CREATED_REPOS_KEY: &str = "created_repos";
+
+pub fn codespace_display_name(job_id: &str) -> String {
+    format!("{}{}", CODESPACE_TAG_PREFIX, job_id)
+}
+
+pub fn deploy_key_title(job_id: &str) -> String {
+    format!("{} [{}]", DEPLOY_KEY_TITLE, job_id)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanKind {
+    Codespace,
+    DeployKey,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanResource {
+    pub kind: OrphanKind,
+    pub repo: String,            // owner/repo
+    pub resource_id: String,     // Codespace name or deploy key id
+    pub job_id: Option<String>,  // None for untagged legacy resources
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepResult {
+    pub resource: OrphanResource,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+/// Remembers a repo the app created so its deploy keys are included in sweeps.
+pub fn record_created_repo<R: tauri::Runtime>(app: &tauri::AppHandle<R>, full_name: &str) {
+    let Ok(store) = app.store(APP_STORE) else { return };
+    let mut repos = created_repos(app);
+    if repos.iter().any(|r| r == full_name) {
+        return;
+    }
+    repos.push(full_name.to_string());
+    store.set(CREATED_REPOS_KEY, serde_json::json!(repos));
+    if let Err(e) = store.save() {
+        println!("Failed to save created repos: {}", e);
+    }
+}
+
+pub fn created_repos<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<String> {
+    app.store(APP_STORE).ok()
+        .and_then(|store| store.get(CREATED_REPOS_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+// A tagged resource is stale unless its job is known and still provisioning.
+fn stale_reason(job_id: &str, jobs: &HashMap<String, JobState>) -> Option<String> {
+    match jobs.get(job_id) {
+        None => Some("No matching job".to_string()),
+        Some(job) if matches!(job.status, JobStatus::Booting | JobStatus::Generating) => None,
+        Some(job) if job.status == JobStatus::Interrupted => Some("Job was interrupted".to_string()),
+        Some(_) => Some("Job finished provisioning".to_string()),
+    }
+}
+
+pub fn scan(
+    gh: &GithubClient,
+    jobs: &HashMap<String, JobState>,
+    repos: &[String],
+) -> Result<Vec<OrphanResource>, String> {
+    let codespaces = gh.list_codespaces()?;
+    let mut deploy_keys = vec![];
+    for repo in repos {
+        let Some((owner, name)) = repo.split_once('/') else { continue };
+        match gh.list_deploy_keys(owner, name) {
+            Ok(keys) => deploy_keys.push((repo.clone(), keys)),
+            Err(e) => println!("Skipping deploy keys for {}: {}", repo, e),
+        }
+    }
+    Ok(find_orphans(codespaces, deploy_keys, jobs))
+}
+
+// The job id in an ephemeral key's title. None if the key isn't ours, Some(None) if it's untagged.
+fn deploy_key_job(title: &str) -> Option<Option<&str>> {
+    let rest = title.strip_prefix(DEPLOY_KEY_TITLE)?;
+    Some(rest.trim().strip_prefix('[').and_then(|r| r.strip_suffix(']')))
+}
+
+fn find_orphans(
+    codespaces: Vec<CodespaceInfo>,
+    deploy_keys: Vec<(String, Vec<DeployKeyInfo>)>,
+    jobs: &HashMap<String, JobState>,
+) -> Vec<OrphanResource> {
+    let mut orphans = vec![];
+
+    for cs in codespaces {
+        let Some(job_id) = cs.display_name.strip_prefix(CODESPACE_TAG_PREFIX) else { continue };
+        if let Some(reason) = stale_reason(job_id, jobs) {
+            orphans.push(OrphanResource {
+                kind: OrphanKind::Codespace,
+                repo: cs.repository,
+                resource_id: cs.name,
+                job_id: Some(job_id.to_string()),
+                reason,
+            });
+        }
+    }
+
+    // An untagged key can't be matched to its job, so it is left alone while
+    // any job on its repo is still running
+    let busy_repos: Vec<&str> = jobs.values()
+        .filter(|job| !job.status.is_terminal())
+        .map(|job| job.github_repo.as_str())
+        .collect();
+
+    for (repo, keys) in deploy_keys {
+        for key in keys {
+            let Some(job_id) = deploy_key_job(&key.title) else { continue };
+            let reason = match job_id {
+                Some(id) => stale_reason(id, jobs),
+                None if busy_repos.contains(&repo.as_str()) => None,
+                None => Some("Untagged ephemeral key".to_string()),
+            };
+            if let Some(reason) = reason {
+                orphans.push(OrphanResource {
+                    kind: OrphanKind::DeployKey,
+                    repo: repo.clone(),
+                    resource_id: key.id.to_string(),
+                    job_id: job_id.map(|s| s.to_string()),
+                    reason,
+                });
+            }
+        }
+    }
+
+    orphans
+}
+
+// "owner/repo" and nothing else: not a URL, not a path
+fn is_repo_name(name: &str) -> bool {
+    let valid = |part: &str| {
+        !part.is_empty() && part != "." && part != ".." && part.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    };
+    matches!(name.split_once('/'), Some((owner, repo)) if valid(owner) && valid(repo))
+}
+
+pub fn scan_for_app<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    gh: &GithubClient,
+) -> Result<Vec<OrphanResource>, String> {
+    let jobs: HashMap<String, JobState> = app.state::<crate::AppState>().jobs.all()
+        .into_iter()
+        .map(|job| (job.id.clone(), job))
+        .collect();
+    let mut repos = created_repos(app);
+    for job in jobs.values() {
+        // A scaffold job's repo is a placeholder until CreateRepo records the real one above
+        if matches!(job.params, Some(JobParams::Scaffold { .. })) || !is_repo_name(&job.github_repo) {
+            continue;
+        }
+        if !repos.contains(&job.github_repo) {
+            repos.push(job.github_repo.clone());
+        }
+    }
+    scan(gh, &jobs, &repos)
+}
+
+/// Deletes the resources the user confirmed, but only those a fresh scan still
+/// reports as orphaned. Anything else is refused and reported as not deleted.
+pub fn sweep_confirmed<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    gh: &GithubClient,
+    confirmed: Vec<OrphanResource>,
+) -> Result<Vec<SweepResult>, String> {
+    let orphans = scan_for_app(app, gh)?;
+    let (orphaned, refused): (Vec<_>, Vec<_>) = confirmed.into_iter().partition(|resource| {
+        orphans.iter().any(|o| o.kind == resource.kind && o.repo == resource.repo && o.resource_id == resource.resource_id)
+    });
+    let mut results = sweep(app, gh, orphaned);
+    results.extend(refused.into_iter().map(|resource| SweepResult {
+        resource,
+        deleted: false,
+        error: Some("Not an orphan in the current scan".to_string()),
+    }));
+    Ok(results)
+}
+
+/// Deletes resources the user confirmed. Every attempt is written to the audit log.
+pub fn sweep<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    gh: &GithubClient,
+    resources: Vec<OrphanResource>,
+) -> Vec<SweepResult> {
+    resources.into_iter().map(|resource| {
+        let res = match resource.kind {
+            OrphanKind::Codespace => gh.delete_codespace(&resource.resource_id),
+            OrphanKind::DeployKey => {
+                let key_id = resource.resource_id.parse::<u64>().map_err(|e| e.to_string());
+                match (key_id, resource.repo.split_once('/')) {
+                    (Ok(id), Some((owner, name))) => gh.remove_deploy_key(owner, name, id),
+                    (Err(e), _) => Err(e),
+                    (_, None) => Err(format!("Invalid repo: {}", resource.repo)),
+                }
+            }
+        };
+
+        audit(app, &resource, &res);
+        SweepResult {
+            deleted: res.is_ok(),
+            error: res.err(),
+            resource,
+        }
+    }).collect()
+}
+
+fn audit<R: tauri::Runtime>(app: &tauri::AppHandle<R>, resource: &OrphanResource, res: &Result<(), String>) {
+    let Ok(dir) = app.path().app_data_dir() else { return };
+    let entry = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "action": "delete",
+        "resource": resource,
+        "ok": res.is_ok(),
+        "error": res.as_ref().err(),
+    });
+
+    let written = std::fs::create_dir_all(&dir)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(dir.join("audit.log")))
+        .and_then(|mut f| writeln!(f, "{}", entry));
+    if let Err(e) = written {
+        println!("Failed to write audit log: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AgentMode;
+
+    fn job(id: &str, repo: &str, status: JobStatus) -> (String, JobState) {
+        let params = JobParams::Uplink { repo_url: repo.to_string(), context: String::new(), mode: AgentMode::Auto, prompt: None, session: Default::default() };
+        (id.to_string(), JobState::new(id.to_string(), repo.to_string(), status, params))
+    }
+
+    fn jobs() -> HashMap<String, JobState> {
+        HashMap::from([
+            job("booting", "o/busy", JobStatus::Booting),
+            job("generating", "o/busy", JobStatus::Generating),
+            job("working", "o/working", JobStatus::Working),
+            job("interrupted", "o/interrupted", JobStatus::Interrupted),
+            job("merged", "o/idle", JobStatus::Merged),
+        ])
+    }
+
+    fn codespace(name: &str, display_name: &str) -> CodespaceInfo {
+        CodespaceInfo { name: name.to_string(), display_name: display_name.to_string(), repository: "o/r".to_string() }
+    }
+
+    fn key(id: u64, title: &str) -> DeployKeyInfo {
+        DeployKeyInfo { id, title: title.to_string() }
+    }
+
+    #[test]
+    fn only_jobs_still_provisioning_keep_their_resources() {
+        let jobs = jobs();
+        let cases = [
+            ("booting", None),
+            ("generating", None),
+            ("working", Some("Job finished provisioning")),
+            ("merged", Some("Job finished provisioning")),
+            ("interrupted", Some("Job was interrupted")),
+            ("unknown", Some("No matching job")),
+        ];
+        for (id, expected) in cases {
+            assert_eq!(stale_reason(id, &jobs).as_deref(), expected, "{}", id);
+        }
+    }
+
+    #[test]
+    fn deploy_key_titles_carry_the_job_id() {
+        let cases = [
+            (deploy_key_title("abc-123"), Some(Some("abc-123"))),
+            ("Command Center Ephemeral".to_string(), Some(None)),
+            ("Command Center Ephemeral [unclosed".to_string(), Some(None)),
+            ("My laptop".to_string(), None),
+            ("command center ephemeral [abc]".to_string(), None),
+        ];
+        for (title, expected) in cases {
+            assert_eq!(deploy_key_job(&title), expected, "{}", title);
+        }
+    }
+
+    #[test]
+    fn untagged_codespaces_are_never_touched() {
+        let codespaces = vec![
+            codespace("cs-live", &codespace_display_name("booting")),
+            codespace("cs-done", &codespace_display_name("merged")),
+            codespace("cs-lost", &codespace_display_name("unknown")),
+            codespace("cs-mine", "my dev box"),
+        ];
+        let orphans = find_orphans(codespaces, vec![], &jobs());
+        let found: Vec<(&str, Option<&str>)> = orphans.iter().map(|o| (o.resource_id.as_str(), o.job_id.as_deref())).collect();
+        assert_eq!(found, vec![("cs-done", Some("merged")), ("cs-lost", Some("unknown"))]);
+        assert!(orphans.iter().all(|o| o.kind == OrphanKind::Codespace && o.repo == "o/r"));
+    }
+
+    #[test]
+    fn untagged_ephemeral_keys_wait_for_jobs_on_their_repo() {
+        let untagged = || vec![key(1, DEPLOY_KEY_TITLE), key(2, "CI key")];
+        let deploy_keys = vec![
+            ("o/busy".to_string(), untagged()),
+            ("o/working".to_string(), untagged()),
+            ("o/interrupted".to_string(), untagged()),
+            ("o/idle".to_string(), untagged()),
+            ("o/busy".to_string(), vec![key(3, &deploy_key_title("booting")), key(4, &deploy_key_title("gone"))]),
+        ];
+        let orphans = find_orphans(vec![], deploy_keys, &jobs());
+        let found: Vec<(&str, &str)> = orphans.iter().map(|o| (o.repo.as_str(), o.resource_id.as_str())).collect();
+        // Working and Interrupted jobs are past provisioning but still in flight
+        assert_eq!(found, vec![("o/idle", "1"), ("o/busy", "4")]);
+        assert_eq!(orphans[0].job_id, None);
+        assert_eq!(orphans[1].job_id.as_deref(), Some("gone"));
+    }
+
+    #[test]
+    fn repo_names_are_owner_slash_repo_only() {
+        let cases = [
+            ("owner/repo", true),
+            ("my-org/my_repo.rs", true),
+            ("https://github.com/owner/repo", false),
+            ("owner/repo/extra", false),
+            ("owner/", false),
+            ("/repo", false),
+            ("repo", false),
+            ("owner/re po", false),
+            ("../etc", false),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(is_repo_name(name), expected, "{}", name);
+        }
+    }
+}
@@ -1,17 +1,59 @@
+use crate::jules::AutomationMode;
 use serde::{Deserialize, Serialize};
 // use std::time::SystemTime;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Booting,           // [Scaffold only] Provisioning Codespace
     Generating,        // [Scaffold only] Running Bash Script
     UploadingContext, // [Universal] Committing AGENTS.md
+    Queued,            // [Universal] Waiting for a Jules slot
     Planning,          // [Universal] Jules Thinking
     WaitingApproval,  // [Universal] Interactive Mode Pause
+    WaitingFeedback,   // [Universal] Jules asked a question, answered with send_agent_message
     Working,           // [Universal] Jules Coding
     PrReady,          // [Universal] Pull Request Created
     Merged,           // [Universal] Job Done
+    Completed,         // [Universal] Jules finished without opening a pull request
+    Paused,            // [Universal] Jules session paused
+    Interrupted,       // [Universal] App closed mid-job, waiting for resume or rollback
+    Failed { reason: String }, // [Universal] Terminal error
+    Cancelled,         // [Universal] Stopped by the user
+}
+
+impl JobStatus {
+    /// Terminal statuses accept no further actions.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobStatus::Merged | JobStatus::Completed | JobStatus::Failed { .. } | JobStatus::Cancelled)
+    }
+
+    /// Statuses the poll loop stops at: Jules is done with the session. PrReady
+    /// is one, but not terminal, since the PR can still be merged or the job cancelled.
+    pub fn ends_session(&self) -> bool {
+        self.is_terminal() || *self == JobStatus::PrReady
+    }
+
+    /// The serialized name, without a failure's reason. Matches `JobStatusKey` in the UI.
+    pub fn key(&self) -> &'static str {
+        match self {
+            JobStatus::Booting => "booting",
+            JobStatus::Generating => "generating",
+            JobStatus::UploadingContext => "uploading_context",
+            JobStatus::Queued => "queued",
+            JobStatus::Planning => "planning",
+            JobStatus::WaitingApproval => "waiting_approval",
+            JobStatus::WaitingFeedback => "waiting_feedback",
+            JobStatus::Working => "working",
+            JobStatus::PrReady => "pr_ready",
+            JobStatus::Merged => "merged",
+            JobStatus::Completed => "completed",
+            JobStatus::Paused => "paused",
+            JobStatus::Interrupted => "interrupted",
+            JobStatus::Failed { .. } => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -37,6 +79,186 @@ pub struct JobState {
     pub jules_session_id: Option<String>,
     pub status: JobStatus,
     pub last_poll: Option<u64>, // Timestamp
+    #[serde(default)]
+    pub approval_pending: bool, // Approved locally, not yet confirmed by a poll
+    #[serde(default)]
+    pub messages: Vec<JobMessage>,
+    #[serde(default)]
+    pub activity_cursor: Option<String>, // Id of the last Jules activity emitted
+    #[serde(default)]
+    pub activity_page_token: Option<String>, // Activity page holding the cursor
+    #[serde(default)]
+    pub session_failure: Option<String>, // Reason from Jules' latest SessionFailed activity
+    #[serde(default)]
+    pub poll_runtime_secs: u64, // Polling time counted against PollPolicy::max_runtime_secs
+    #[serde(default)]
+    pub pr_details: Option<PrDetails>,
+    #[serde(default)]
+    pub plans: Vec<Plan>, // Every revision, oldest first
+    #[serde(default)]
+    pub warnings: Vec<String>, // Non-fatal problems, e.g. a cleanup that failed
+    #[serde(default)]
+    pub failure: Option<JobFailure>,
+    #[serde(default)]
+    pub params: Option<JobParams>, // What the job was started with
+    #[serde(default)]
+    pub history: Vec<StatusChange>,
+    #[serde(default)]
+    pub created_at: u64, // Unix seconds
+    #[serde(default)]
+    pub batch_id: Option<String>, // Set on the children of start_batch_uplink
+    #[serde(default)]
+    pub schedule_id: Option<String>, // Set on jobs started by a schedule
+    #[serde(default)]
+    pub merge_policy: Option<MergePolicy>, // Overrides the repo's policy
+    #[serde(default)]
+    pub merge_decided: bool, // Auto-merge merged the PR or settled on not merging it
+}
+
+impl JobState {
+    pub fn new(id: String, github_repo: String, status: JobStatus, params: JobParams) -> Self {
+        JobState {
+            id,
+            github_repo,
+            jules_session_id: None,
+            status,
+            last_poll: None,
+            approval_pending: false,
+            messages: vec![],
+            activity_cursor: None,
+            activity_page_token: None,
+            session_failure: None,
+            poll_runtime_secs: 0,
+            pr_details: None,
+            plans: vec![],
+            warnings: vec![],
+            failure: None,
+            params: Some(params),
+            history: vec![],
+            created_at: chrono::Utc::now().timestamp() as u64,
+            batch_id: None,
+            schedule_id: None,
+            merge_policy: None,
+            merge_decided: false,
+        }
+    }
+}
+
+/// The user's choices for a job's Jules session. Unset fields keep the job's defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionSettings {
+    pub title: Option<String>,
+    pub starting_branch: Option<String>,         // Defaults to the repo's default branch
+    pub automation_mode: Option<AutomationMode>, // Defaults to AUTO_CREATE_PR
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum JobParams {
+    Scaffold {
+        name: String,
+        recipe_id: String,
+        context: String,
+        mode: AgentMode,
+        #[serde(default)]
+        prompt: Option<String>, // None = scaffold_engine::DEFAULT_PROMPT
+        #[serde(default)]
+        session: SessionSettings,
+    },
+    Uplink {
+        repo_url: String,
+        context: String,
+        mode: AgentMode,
+        #[serde(default)]
+        prompt: Option<String>, // None = uplink_engine::DEFAULT_PROMPT
+        #[serde(default)]
+        session: SessionSettings,
+    },
+    Import { session_id: String },
+}
+
+impl JobParams {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobParams::Scaffold { .. } => "scaffold",
+            JobParams::Uplink { .. } => "uplink",
+            JobParams::Import { .. } => "import",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusChange {
+    pub status: JobStatus,
+    pub at: String, // RFC 3339
+}
+
+impl StatusChange {
+    pub fn now(status: JobStatus) -> Self {
+        StatusChange { status, at: chrono::Utc::now().to_rfc3339() }
+    }
+}
+
+// Serialized as GitHub's merge_method
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeMethod {
+    Merge,
+    #[default]
+    Squash,
+    Rebase,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_check_timeout() -> u64 {
+    60
+}
+
+/// When an Auto mode job's PR may be merged without a human.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MergePolicy {
+    #[serde(default)]
+    pub method: MergeMethod,
+    #[serde(default = "default_true")]
+    pub require_checks: bool,
+    #[serde(default = "default_check_timeout")]
+    pub check_timeout_mins: u64,
+    #[serde(default)]
+    pub max_changed_lines: Option<u64>, // Additions + deletions
+    #[serde(default)]
+    pub allowed_paths: Vec<String>, // e.g. "docs/", "Cargo.toml", "*.md". Empty = any
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobFailure {
+    pub error: String,
+    pub step: Option<String>, // Step name, None when the job died outside a step
+    pub retryable: bool,
+    pub artifacts: Box<PartialArtifacts>, // Boxed to keep Result<_, JobFailure> small
+}
+
+/// What the job left behind before it failed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialArtifacts {
+    pub created_repo: Option<String>, // owner/repo
+    pub session_id: Option<String>,
+    pub pr_details: Option<PrDetails>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JobMessage {
+    pub text: String,
+    pub sent_at: String, // RFC 3339
+    pub acknowledged: bool,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -46,7 +268,71 @@ pub struct JobUpdateEvent {
     pub status: JobStatus,
     pub logs: Vec<String>,
     pub pr_details: Option<PrDetails>,
-    pub plan: Option<String>,
+    pub plan: Option<Plan>,
+    pub activities: Vec<Activity>,
+    pub failure: Option<JobFailure>,
+}
+
+impl JobUpdateEvent {
+    /// An update that only sets the status and adds log lines.
+    pub fn new(id: impl Into<String>, status: JobStatus, logs: Vec<String>) -> Self {
+        JobUpdateEvent {
+            id: id.into(),
+            status,
+            logs,
+            pr_details: None,
+            plan: None,
+            activities: vec![],
+            failure: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Plan {
+    pub id: String,
+    pub steps: Vec<PlanStep>,
+    pub create_time: String, // RFC 3339
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanStep {
+    pub index: u32,
+    pub title: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    pub id: String,
+    pub create_time: String,
+    pub originator: String, // "agent", "user" or "system"
+    pub kind: ActivityKind,
+    pub artifacts: Vec<Artifact>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum ActivityKind {
+    PlanGenerated { plan: Plan },
+    PlanApproved { plan_id: String },
+    ProgressUpdated { title: String, description: String },
+    AgentMessaged { message: String },
+    UserMessaged { message: String },
+    SessionCompleted,
+    SessionFailed { reason: String },
+    Other,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum Artifact {
+    BashOutput { command: String, output: String, exit_code: i64 },
+    ChangeSet { files: Vec<String> },
+    Media,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -1,109 +1,86 @@
-use crate::types::{AgentMode, JobStatus, JobUpdateEvent};
-use crate::github::GithubClient;
+use crate::auth;
+use crate::dry_run::{self, DryRunReport};
 use crate::jules::JulesClient;
-use tauri::Emitter;
-use std::thread;
-use std::time::Duration;
+use crate::types::{AgentMode, JobFailure, JobParams, JobState, JobStatus, SessionSettings};
+use crate::pipeline::{self, JobContext, Pipeline};
+use crate::prompts;
+use tauri::Manager;
+use crate::steps::{PollSession, StartSession, UploadContext, VerifyAccess};
 
-fn emit_update(app: &tauri::AppHandle, event: JobUpdateEvent) {
-    if let Err(e) = app.emit("JOB_UPDATE", event) {
-        println!("Failed to emit event: {}", e);
-    }
-}
+/// Used when the job doesn't bring its own prompt.
+pub const DEFAULT_PROMPT: &str = "Read AGENTS.md and execute instructions.";
 
-pub fn run_uplink_job(
-    job_id: String,
-    repo_url: String,
-    context: String,
-    mode: AgentMode,
-    gh_token: String,
-    google_token: String,
-    app_handle: tauri::AppHandle
-) -> Result<(), String> {
-    // 1. Parse Repo
+/// Splits "https://github.com/owner/repo" (or "owner/repo") into its owner and name.
+pub fn parse_repo_url(repo_url: &str) -> Result<(String, String), String> {
     let parts: Vec<&str> = repo_url.trim_end_matches('/').split('/').collect();
     if parts.len() < 2 {
         return Err("Invalid Repo URL".to_string());
     }
-    let repo_name = parts.last().unwrap();
-    let owner = parts[parts.len() - 2];
+    let repo_name = parts[parts.len() - 1].trim_end_matches(".git");
+    Ok((parts[parts.len() - 2].to_string(), repo_name.to_string()))
+}
 
-    // Emit Booting/Connect
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::Booting,
-        logs: vec!["Verifying repository access...".to_string()],
-        pr_details: None,
-        plan: None,
-    });
+/// Existing repo -> AGENTS.md -> Jules.
+pub fn uplink_steps(owner: &str, repo: &str, context: &str, prompt: &str, session: &SessionSettings) -> Pipeline {
+    vec![
+        Box::new(VerifyAccess { owner: owner.to_string(), repo: repo.to_string() }),
+        Box::new(UploadContext { context: context.to_string() }),
+        Box::new(StartSession {
+            prompt: prompt.to_string(),
+            title: format!("Command Center: uplink {}/{}", owner, repo),
+            recipe: None,
+            session: session.clone(),
+        }),
+        Box::new(PollSession),
+    ]
+}
 
-    // 2. Github Client
-    let gh = GithubClient::new(gh_token.clone());
+pub fn run_uplink_job(ctx: JobContext, repo_url: &str, context: &str, prompt: &str, session: &SessionSettings) -> Result<(), JobFailure> {
+    let (owner, repo) = parse_repo_url(repo_url).map_err(JobFailure::before_steps)?;
+    pipeline::run(ctx, uplink_steps(&owner, &repo, context, prompt, session), JobStatus::UploadingContext)
+}
 
-    // 3. Verify Access
-    if !gh.check_repo_access(owner, repo_name)? {
-        return Err("No write access to repo".to_string());
+/// Creates the job and starts it in the background. Returns the job id.
+/// Must be called on a blocking thread.
+pub fn start_job(app: &tauri::AppHandle, repo_url: String, context: String, mode: AgentMode, prompt: Option<String>, session: SessionSettings) -> Result<String, String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty());
+    if let Some(prompt) = &prompt {
+        prompts::validate(prompt)?;
     }
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
 
-    // 4. Update Context
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::UploadingContext,
-        logs: vec!["Syncing AGENTS.md...".to_string()],
-        pr_details: None,
-        plan: None,
-    });
+    // Fail before the job exists if Jules can't see the repo
+    let (owner, repo) = parse_repo_url(&repo_url)?;
+    JulesClient::new(jules_config.clone()).require_source(&owner, &repo)?;
 
-    gh.update_file(owner, repo_name, "AGENTS.md", &context, "Update AGENTS.md via Command Center")?;
+    let job_id = uuid::Uuid::new_v4().to_string();
+    app.state::<crate::AppState>().jobs.insert(JobState::new(
+        job_id.clone(),
+        format!("{}/{}", owner, repo),
+        JobStatus::UploadingContext,
+        JobParams::Uplink { repo_url: repo_url.clone(), context: context.clone(), mode: mode.clone(), prompt: prompt.clone(), session: session.clone() },
+    ));
 
-    // 5. Start Jules
-    emit_update(&app_handle, JobUpdateEvent {
-        id: job_id.clone(),
-        status: JobStatus::Planning,
-        logs: vec!["Starting AI Session...".to_string()],
-        pr_details: None,
-        plan: None,
+    let handle = app.clone();
+    let id = job_id.clone();
+    pipeline::spawn(app.clone(), job_id.clone(), move || {
+        let ctx = JobContext::new(handle, id, gh_token, jules_config, mode);
+        let prompt = prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+        run_uplink_job(ctx, &repo_url, &context, prompt, &session)
     });
+    Ok(job_id)
+}
 
-    let jules = JulesClient::new(google_token);
-    let session_id = jules.start_session(
-        &format!("github.com/{}/{}", owner, repo_name),
-        "Read AGENTS.md and execute instructions.",
-         matches!(mode, AgentMode::Interactive)
-    )?;
-
-     // 6. Polling Loop
-    loop {
-        thread::sleep(Duration::from_secs(5));
-
-        match jules.poll_session(&session_id) {
-             Ok((status, pr, plan)) => {
-                let mut logs = vec![];
-                match status {
-                    JobStatus::Planning => logs.push("Jules is thinking...".to_string()),
-                    JobStatus::Working => logs.push("Jules is working on code...".to_string()),
-                    JobStatus::WaitingApproval => logs.push("Plan ready for review.".to_string()),
-                    JobStatus::PrReady => logs.push("Pull Request created.".to_string()),
-                    _ => {}
-                }
-
-                emit_update(&app_handle, JobUpdateEvent {
-                    id: job_id.clone(),
-                    status: status.clone(),
-                    logs,
-                    pr_details: pr,
-                    plan,
-                });
-
-                if matches!(status, JobStatus::PrReady | JobStatus::Merged) {
-                    break;
-                }
-            }
-            Err(e) => {
-                println!("Polling error: {}", e);
-            }
-        }
-    }
+/// Resolves the job without creating it and reports what it would do.
+/// Must be called on a blocking thread.
+pub fn dry_run(app: &tauri::AppHandle, repo_url: &str, context: &str, mode: AgentMode, prompt: Option<&str>, session: &SessionSettings) -> Result<DryRunReport, String> {
+    let prompt = prompt.filter(|p| !p.trim().is_empty()).unwrap_or(DEFAULT_PROMPT);
+    prompts::validate(prompt)?;
+    let gh_token = auth::get_github_token(app).ok_or("GitHub not authenticated")?;
+    let jules_config = auth::get_jules_config(app).ok_or("Jules not authenticated")?;
 
-    Ok(())
+    let (owner, repo) = parse_repo_url(repo_url)?;
+    let ctx = JobContext::for_dry_run(app.clone(), gh_token, jules_config, mode);
+    Ok(dry_run::plan(ctx, uplink_steps(&owner, &repo, context, prompt, session)))
 }
@@ -1,5 +1,7 @@
-import React from 'react';
+import React, { useEffect, useState } from 'react';
 import { useJobs } from '../../context/JobContext';
+import { TauriService } from '../../services/tauriService';
+import { QuotaUsage } from '../../types';
 import JobCard from './JobCard';
 import { Plus, Command } from 'lucide-react';
 
@@ -10,6 +12,15 @@ interface DashboardProps {
 
 const Dashboard: React.FC<DashboardProps> = ({ onNew, onUplink }) => {
   const { jobs } = useJobs();
+  const [quota, setQuota] = useState<QuotaUsage | null>(null);
+
+  // Refresh Jules quota usage whenever jobs change, and periodically for the daily window
+  useEffect(() => {
+    const refresh = () => TauriService.getJulesQuota().then(setQuota).catch(() => setQuota(null));
+    refresh();
+    const timer = setInterval(refresh, 30000);
+    return () => clearInterval(timer);
+  }, [jobs]);
 
   // Sort: Active first, then by date
   const sortedJobs = [...jobs].sort((a, b) => {
@@ -26,8 +37,19 @@ const Dashboard: React.FC<DashboardProps> = ({ onNew, onUplink }) => {
           <Command className="text-indigo-500" />
           Mission Control
         </h1>
-        <div className="text-xs font-mono text-slate-500 bg-slate-900 px-2 py-1 rounded border border-slate-800">
-          v4.0
+        <div className="flex items-center gap-2">
+          {quota && (
+            <div
+              className="text-xs font-mono text-slate-400 bg-slate-900 px-2 py-1 rounded border border-slate-800"
+              title={quota.nextAvailable ? `Next slot: ${new Date(quota.nextAvailable).toLocaleString()}` : undefined}
+            >
+              {quota.active}/{quota.limits.maxConcurrent} running · {quota.startedToday}/{quota.limits.maxDaily} today
+              {quota.queued > 0 && ` · ${quota.queued} queued`}
+            </div>
+          )}
+          <div className="text-xs font-mono text-slate-500 bg-slate-900 px-2 py-1 rounded border border-slate-800">
+            v4.0
+          </div>
         </div>
       </header>
 
@@ -1,5 +1,5 @@
-import React, { useRef, useEffect } from 'react';
-import { Job, JobStatus } from '../../types';
+import React, { useRef, useEffect, useState } from 'react';
+import { Job, JobStatusKey, statusKey } from '../../types';
 import {
   GitPullRequest,
   Terminal,
@@ -23,31 +23,46 @@ interface JobCardProps {
   job: Job;
 }
 
-const StatusColors: Record<JobStatus, string> = {
+const StatusColors: Record<JobStatusKey, string> = {
   booting: 'text-yellow-500 border-yellow-500/30 bg-yellow-500/10',
   generating: 'text-yellow-500 border-yellow-500/30 bg-yellow-500/10',
   uploading_context: 'text-blue-400 border-blue-400/30 bg-blue-400/10',
+  queued: 'text-slate-400 border-slate-600 bg-slate-800/50',
   planning: 'text-blue-400 border-blue-400/30 bg-blue-400/10',
   waiting_approval: 'text-orange-500 border-orange-500/30 bg-orange-500/10',
+  waiting_feedback: 'text-orange-500 border-orange-500/30 bg-orange-500/10',
   working: 'text-blue-400 border-blue-400/30 bg-blue-400/10',
   pr_ready: 'text-green-500 border-green-500/30 bg-green-500/10',
   merged: 'text-slate-500 border-slate-600 bg-slate-800/50',
+  completed: 'text-slate-500 border-slate-600 bg-slate-800/50',
+  paused: 'text-orange-500 border-orange-500/30 bg-orange-500/10',
+  interrupted: 'text-orange-500 border-orange-500/30 bg-orange-500/10',
+  failed: 'text-red-500 border-red-500/30 bg-red-500/10',
+  cancelled: 'text-slate-500 border-slate-600 bg-slate-800/50',
 };
 
-const StatusLabels: Record<JobStatus, string> = {
+const StatusLabels: Record<JobStatusKey, string> = {
   booting: 'Booting Infra',
   generating: 'Scaffolding',
   uploading_context: 'Syncing Context',
+  queued: 'Queued',
   planning: 'Jules Planning',
   waiting_approval: 'Needs Approval',
+  waiting_feedback: 'Needs Reply',
   working: 'Agent Coding',
   pr_ready: 'PR Ready',
   merged: 'Merged',
+  completed: 'Done, No PR',
+  paused: 'Paused',
+  interrupted: 'Interrupted',
+  failed: 'Failed',
+  cancelled: 'Cancelled',
 };
 
 const JobCard: React.FC<JobCardProps> = ({ job }) => {
-  const { approvePlan, mergePR } = useJobs();
+  const { approvePlan, sendMessage, mergePR } = useJobs();
   const logRef = useRef<HTMLDivElement>(null);
+  const [reply, setReply] = useState('');
 
   // Auto scroll logs
   useEffect(() => {
@@ -60,13 +75,15 @@ const JobCard: React.FC<JobCardProps> = ({ job }) => {
     ? IconMap[job.generatorIcon]
     : Box;
 
-  const isTerminal = job.status === 'merged';
-  const colorClass = StatusColors[job.status];
+  const status = statusKey(job.status);
+  // Mirrors JobStatus::is_terminal: a ready PR can still be merged or cancelled
+  const isTerminal = status === 'merged' || status === 'completed' || status === 'failed' || status === 'cancelled';
+  const colorClass = StatusColors[status];
 
   // Progress Logic (Visual only)
   const getProgress = () => {
-    const stages: JobStatus[] = ['booting', 'generating', 'uploading_context', 'planning', 'waiting_approval', 'working', 'pr_ready', 'merged'];
-    const index = stages.indexOf(job.status);
+    const stages: JobStatusKey[] = ['booting', 'generating', 'uploading_context', 'planning', 'waiting_approval', 'working', 'pr_ready', 'merged'];
+    const index = stages.indexOf(status);
     return Math.max(5, ((index + 1) / stages.length) * 100);
   };
 
@@ -87,7 +104,7 @@ const JobCard: React.FC<JobCardProps> = ({ job }) => {
               ) : (
                  <div className="w-2 h-2 rounded-full bg-current" />
               )}
-              {StatusLabels[job.status]}
+              {StatusLabels[status]}
             </div>
           </div>
         </div>
@@ -140,6 +157,29 @@ const JobCard: React.FC<JobCardProps> = ({ job }) => {
           </div>
         )}
 
+        {/* Action: Reply Needed */}
+        {job.status === 'waiting_feedback' && (
+          <div className="bg-orange-500/10 border border-orange-500/20 rounded-lg p-3">
+            <h4 className="text-orange-400 font-bold text-sm mb-1">Jules Has a Question</h4>
+            <p className="text-xs text-orange-300/80 mb-3">Reply to let Jules continue. The question is in the console.</p>
+            <div className="flex gap-2">
+              <input
+                value={reply}
+                onChange={e => setReply(e.target.value)}
+                className="flex-1 bg-slate-800 border border-slate-700 rounded-md px-2 text-sm text-slate-100"
+                placeholder="Your reply"
+              />
+              <button
+                onClick={() => sendMessage(job.id, reply).then(() => setReply(''))}
+                disabled={!reply.trim()}
+                className="bg-orange-600 hover:bg-orange-500 disabled:opacity-50 text-white px-3 py-2 rounded-md text-sm font-semibold transition-colors"
+              >
+                Send
+              </button>
+            </div>
+          </div>
+        )}
+
         {/* Action: PR Ready */}
         {job.status === 'pr_ready' && job.prDetails && (
           <div className="bg-green-500/10 border border-green-500/20 rounded-lg p-3">
@@ -154,6 +194,24 @@ const JobCard: React.FC<JobCardProps> = ({ job }) => {
           </div>
         )}
 
+        {/* State: Failed */}
+        {status === 'failed' && (
+          <div className="bg-red-500/10 border border-red-500/20 rounded-lg p-3">
+            <h4 className="text-red-400 font-bold text-sm mb-1">
+              {job.failure?.step ? `Failed at ${job.failure.step}` : 'Job Failed'}
+            </h4>
+            <p className="text-xs text-red-300/80 break-words">
+              {job.failure?.error ?? (typeof job.status === 'object' ? job.status.failed.reason : '')}
+            </p>
+            {job.failure?.artifacts.createdRepo && (
+              <p className="text-xs text-slate-400 mt-2">Repository kept: {job.failure.artifacts.createdRepo}</p>
+            )}
+            {job.failure?.retryable && (
+              <p className="text-xs text-slate-400 mt-1">This looks temporary. Starting the job again may succeed.</p>
+            )}
+          </div>
+        )}
+
         {/* State: Merged */}
         {job.status === 'merged' && (
            <div className="text-center py-2">
@@ -1,8 +1,9 @@
 import React, { useState } from 'react';
-import { AgentMode } from '../../types';
+import { AgentMode, SourceResolution } from '../../types';
+import { TauriService } from '../../services/tauriService';
 import { useJobs } from '../../context/JobContext';
 import ContextEditor from '../Shared/ContextEditor';
-import { ArrowLeft, Plug } from 'lucide-react';
+import { ArrowLeft, Plug, AlertTriangle } from 'lucide-react';
 
 interface Props {
   onBack: () => void;
@@ -14,10 +15,27 @@ const UplinkWizard: React.FC<Props> = ({ onBack, onComplete }) => {
   const [repoName, setRepoName] = useState('');
   const [mode, setMode] = useState<AgentMode>('interactive');
   const [context, setContext] = useState('');
+  const [checking, setChecking] = useState(false);
+  const [missingSource, setMissingSource] = useState<SourceResolution | null>(null);
 
-  const handleSubmit = (e: React.FormEvent) => {
+  const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     if (!repoName.trim()) return;
+
+    // Don't create a job Jules can never start
+    setChecking(true);
+    try {
+      const resolution = await TauriService.checkJulesSource(repoName);
+      if (resolution.status === 'not_installed') {
+        setMissingSource(resolution);
+        return;
+      }
+    } catch (err) {
+      console.error("Failed to check Jules source", err);
+    } finally {
+      setChecking(false);
+    }
+
     addUplinkJob(repoName, context, mode);
     onComplete();
   };
@@ -39,11 +57,23 @@ const UplinkWizard: React.FC<Props> = ({ onBack, onComplete }) => {
           <input
             type="text"
             value={repoName}
-            onChange={(e) => setRepoName(e.target.value)}
+            onChange={(e) => { setRepoName(e.target.value); setMissingSource(null); }}
             placeholder="owner/repository"
             className="w-full bg-slate-900 border border-slate-700 rounded-lg p-3 text-white text-sm focus:ring-2 focus:ring-indigo-500 focus:outline-none"
             autoFocus
           />
+          {missingSource?.status === 'not_installed' && (
+            <div className="flex items-start gap-2 text-xs text-amber-400 pt-1">
+              <AlertTriangle size={14} className="shrink-0 mt-0.5" />
+              <span>
+                Jules is not installed on {missingSource.repo}.{' '}
+                <a href={missingSource.installUrl} target="_blank" rel="noreferrer" className="underline">
+                  Install the Jules GitHub app
+                </a>{' '}
+                and try again.
+              </span>
+            </div>
+          )}
         </div>
 
         {/* Agent Mode */}
@@ -74,7 +104,7 @@ const UplinkWizard: React.FC<Props> = ({ onBack, onComplete }) => {
         <div className="mt-auto pt-4">
           <button
             type="submit"
-            disabled={!repoName.trim()}
+            disabled={!repoName.trim() || checking}
             className="w-full bg-indigo-600 disabled:bg-slate-800 disabled:text-slate-500 hover:bg-indigo-500 text-white py-4 rounded-xl font-bold text-lg shadow-lg flex items-center justify-center gap-2 transition-all active:scale-[0.98]"
           >
             <Plug size={20} />
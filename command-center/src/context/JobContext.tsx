@@ -8,6 +8,7 @@ interface JobContextType {
   addUplinkJob: (repoName: string, context: string, mode: AgentMode) => Promise<void>;
   approvePlan: (jobId: string) => Promise<void>;
   refinePlan: (jobId: string, feedback: string) => Promise<void>;
+  sendMessage: (jobId: string, message: string) => Promise<void>;
   mergePR: (jobId: string) => Promise<void>;
 }
 
@@ -71,7 +72,8 @@ export const JobProvider: React.FC<{ children: React.ReactNode }> = ({ children
 
   const approvePlan = async (jobId: string) => {
     await TauriService.approvePlan(jobId);
-    setJobs(prev => prev.map(job => job.id === jobId ? { ...job, status: 'working', logs: [...job.logs, '>> Plan approved.'] } : job));
+    // The status comes from the next JOB_UPDATE; Jules may still be planning
+    setJobs(prev => prev.map(job => job.id === jobId ? { ...job, logs: [...job.logs, '>> Plan approved.'] } : job));
   };
 
   const refinePlan = async (jobId: string, feedback: string) => {
@@ -79,13 +81,18 @@ export const JobProvider: React.FC<{ children: React.ReactNode }> = ({ children
       // Optimistic update?
   };
 
+  const sendMessage = async (jobId: string, message: string) => {
+    // The backend logs the message in a JOB_UPDATE and leaves the status to the poll loop
+    await TauriService.sendMessage(jobId, message);
+  };
+
   const mergePR = async (jobId: string) => {
     await TauriService.mergePR(jobId);
     setJobs(prev => prev.map(job => job.id === jobId ? { ...job, status: 'merged', logs: [...job.logs, '>> Merged PR.'] } : job));
   };
 
   return (
-    <JobContext.Provider value={{ jobs, addScaffoldJob, addUplinkJob, approvePlan, refinePlan, mergePR: mergePR }}>
+    <JobContext.Provider value={{ jobs, addScaffoldJob, addUplinkJob, approvePlan, refinePlan, sendMessage, mergePR: mergePR }}>
       {children}
     </JobContext.Provider>
   );
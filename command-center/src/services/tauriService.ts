@@ -1,6 +1,6 @@
 // services/tauriService.ts
 import { invoke } from '@tauri-apps/api/core';
-import { AgentMode } from '../types';
+import { AgentMode, Batch, DryRunReport, BatchProgress, BatchTarget, JobPage, MissedRuns, PromptTemplate, Schedule, ScheduledTask, JobQuery, JobRecord, JulesSource, MergePolicy, OrphanResource, PlanRevision, PollPolicy, QuotaLimits, QuotaUsage, SessionSettings, SessionSummary, SourceResolution, SweepResult } from '../types';
 
 export const TauriService = {
   checkAuthStatus: async () => {
@@ -11,12 +11,51 @@ export const TauriService = {
     return await invoke('initiate_google_login');
   },
 
-  startScaffoldJob: async (name: string, recipeId: string, context: string, mode: AgentMode): Promise<string> => {
-    return await invoke('start_scaffold_job', { name, recipeId, context, mode });
+  // `prompt` may use {repo}, {recipe} and {default_branch}; omitted = the default prompt
+  startScaffoldJob: async (name: string, recipeId: string, context: string, mode: AgentMode, prompt?: string, session?: SessionSettings): Promise<string> => {
+    return await invoke('start_scaffold_job', { name, recipeId, context, mode, prompt, session });
   },
 
-  startUplinkJob: async (repoUrl: string, context: string, mode: AgentMode): Promise<string> => {
-    return await invoke('start_uplink_job', { repoUrl, context, mode });
+  startUplinkJob: async (repoUrl: string, context: string, mode: AgentMode, prompt?: string, session?: SessionSettings): Promise<string> => {
+    return await invoke('start_uplink_job', { repoUrl, context, mode, prompt, session });
+  },
+
+  // Resolve a job without creating anything
+  dryRunScaffoldJob: async (name: string, recipeId: string, context: string, mode: AgentMode, prompt?: string, session?: SessionSettings): Promise<DryRunReport> => {
+    return await invoke('start_scaffold_job', { name, recipeId, context, mode, prompt, session, dryRun: true });
+  },
+
+  dryRunUplinkJob: async (repoUrl: string, context: string, mode: AgentMode, prompt?: string, session?: SessionSettings): Promise<DryRunReport> => {
+    return await invoke('start_uplink_job', { repoUrl, context, mode, prompt, session, dryRun: true });
+  },
+
+  listSchedules: async (): Promise<Schedule[]> => {
+    return await invoke('list_schedules');
+  },
+
+  createSchedule: async (name: string, cron: string, task: ScheduledTask, missedRuns?: MissedRuns): Promise<Schedule> => {
+    return await invoke('create_schedule', { name, cron, task, missedRuns });
+  },
+
+  setSchedulePaused: async (scheduleId: string, paused: boolean): Promise<Schedule> => {
+    return await invoke('set_schedule_paused', { scheduleId, paused });
+  },
+
+  deleteSchedule: async (scheduleId: string) => {
+    return await invoke('delete_schedule', { scheduleId });
+  },
+
+  listPromptTemplates: async (): Promise<PromptTemplate[]> => {
+    return await invoke('list_prompt_templates');
+  },
+
+  // Leave `id` empty to add a new template
+  savePromptTemplate: async (template: PromptTemplate): Promise<PromptTemplate> => {
+    return await invoke('save_prompt_template', { template });
+  },
+
+  deletePromptTemplate: async (templateId: string) => {
+    return await invoke('delete_prompt_template', { templateId });
   },
 
   approvePlan: async (jobId: string) => {
@@ -27,7 +66,112 @@ export const TauriService = {
     return await invoke('refine_agent_plan', { jobId, feedback });
   },
 
+  getPlanRevisions: async (jobId: string): Promise<PlanRevision[]> => {
+    return await invoke('get_plan_revisions', { jobId });
+  },
+
+  sendMessage: async (jobId: string, message: string) => {
+    return await invoke('send_agent_message', { jobId, message });
+  },
+
+  cancelJob: async (jobId: string) => {
+    return await invoke('cancel_job', { jobId });
+  },
+
+  // Empty prompt = the default uplink prompt
+  startBatchUplink: async (target: BatchTarget, context: string, prompt: string, mode: AgentMode): Promise<Batch> => {
+    return await invoke('start_batch_uplink', { target, context, prompt, mode });
+  },
+
+  getBatchProgress: async (batchId: string): Promise<BatchProgress> => {
+    return await invoke('get_batch_progress', { batchId });
+  },
+
+  listBatches: async (): Promise<Batch[]> => {
+    return await invoke('list_batches');
+  },
+
+  listJobs: async (query?: JobQuery): Promise<JobPage> => {
+    return await invoke('list_jobs', { query });
+  },
+
+  getJob: async (jobId: string): Promise<JobRecord> => {
+    return await invoke('get_job', { jobId });
+  },
+
+  getJobLogs: async (jobId: string, tail?: number): Promise<string[]> => {
+    return await invoke('get_job_logs', { jobId, tail });
+  },
+
+  // Only for interrupted jobs: resume starts provisioning over (or reattaches to
+  // the Jules session), rollback cleans up what the interrupted run left behind
+  resumeJob: async (jobId: string) => {
+    return await invoke('resume_job', { jobId });
+  },
+
+  rollbackJob: async (jobId: string) => {
+    return await invoke('rollback_job', { jobId });
+  },
+
   mergePR: async (jobId: string) => {
     return await invoke('merge_pull_request', { jobId });
+  },
+
+  // null clears the job's policy, falling back to its repo's
+  setJobMergePolicy: async (jobId: string, policy: MergePolicy | null) => {
+    return await invoke('set_job_merge_policy', { jobId, policy });
+  },
+
+  getRepoMergePolicies: async (): Promise<Record<string, MergePolicy>> => {
+    return await invoke('get_repo_merge_policies');
+  },
+
+  setRepoMergePolicy: async (repo: string, policy: MergePolicy | null) => {
+    return await invoke('set_repo_merge_policy', { repo, policy });
+  },
+
+  listJulesSessions: async (): Promise<SessionSummary[]> => {
+    return await invoke('list_jules_sessions');
+  },
+
+  // Empty values reset to the default endpoint / Google sign-in
+  configureJules: async (baseUrl?: string, apiKey?: string) => {
+    return await invoke('configure_jules', { baseUrl, apiKey });
+  },
+
+  checkJulesSource: async (repoUrl: string): Promise<SourceResolution> => {
+    return await invoke('check_jules_source', { repoUrl });
+  },
+
+  listJulesSources: async (): Promise<JulesSource[]> => {
+    return await invoke('list_jules_sources');
+  },
+
+  importSession: async (sessionId: string): Promise<string> => {
+    return await invoke('import_session', { sessionId });
+  },
+
+  getJulesQuota: async (): Promise<QuotaUsage> => {
+    return await invoke('get_jules_quota');
+  },
+
+  setJulesLimits: async (limits: QuotaLimits) => {
+    return await invoke('set_jules_limits', { limits });
+  },
+
+  getPollPolicy: async (): Promise<PollPolicy> => {
+    return await invoke('get_poll_policy');
+  },
+
+  setPollPolicy: async (policy: PollPolicy) => {
+    return await invoke('set_poll_policy', { policy });
+  },
+
+  scanOrphanedResources: async (): Promise<OrphanResource[]> => {
+    return await invoke('scan_orphaned_resources');
+  },
+
+  deleteOrphanedResources: async (resources: OrphanResource[]): Promise<SweepResult[]> => {
+    return await invoke('delete_orphaned_resources', { resources });
   }
 };
@@ -1,13 +1,28 @@
 export type JobType = 'scaffold' | 'existing_uplink';
-export type JobStatus =
+export type JobStatusKey =
   | 'booting'           // [Scaffold only] Provisioning Codespace
   | 'generating'        // [Scaffold only] Running Bash Script
   | 'uploading_context' // [Universal] Committing AGENTS.md
+  | 'queued'            // [Universal] Waiting for a Jules slot
   | 'planning'          // [Universal] Jules Thinking
   | 'waiting_approval'  // [Universal] Interactive Mode Pause
+  | 'waiting_feedback'  // [Universal] Jules asked a question, answer with sendMessage
   | 'working'           // [Universal] Jules Coding
   | 'pr_ready'          // [Universal] Pull Request Created
-  | 'merged';           // [Universal] Job Done
+  | 'merged'            // [Universal] Job Done
+  | 'completed'         // [Universal] Jules finished without opening a pull request
+  | 'paused'            // [Universal] Jules session paused
+  | 'interrupted'       // [Universal] App closed mid-job, waiting for resume or rollback
+  | 'failed'            // [Universal] Terminal error
+  | 'cancelled';        // [Universal] Stopped by the user
+
+// Mirrors the Rust enum: unit variants are plain strings, `failed` carries its reason.
+export type JobStatus =
+  | Exclude<JobStatusKey, 'failed'>
+  | { failed: { reason: string } };
+
+export const statusKey = (status: JobStatus): JobStatusKey =>
+  typeof status === 'string' ? status : 'failed';
 
 export type AgentMode = 'auto' | 'interactive';
 
@@ -35,6 +50,22 @@ export interface Job {
 
   // Details for the PR Ready state
   prDetails?: PrDetails;
+
+  // Set when the job ends in the failed state
+  failure?: JobFailure;
+}
+
+export interface PartialArtifacts {
+  createdRepo?: string; // "owner/repo"
+  sessionId?: string;
+  prDetails?: Omit<PrDetails, 'filesChanged'>;
+}
+
+export interface JobFailure {
+  error: string;
+  step?: string; // e.g. "create_codespace"
+  retryable: boolean;
+  artifacts: PartialArtifacts;
 }
 
 export interface GeneratorRecipe {
@@ -48,4 +79,265 @@ export interface GeneratorRecipe {
 export interface ContextTemplate {
   label: string;
   content: string;
-}
\ No newline at end of file
+}
+
+export interface OrphanResource {
+  kind: 'codespace' | 'deploy_key';
+  repo: string; // "owner/repo"
+  resourceId: string;
+  jobId?: string;
+  reason: string;
+}
+
+export interface SweepResult {
+  resource: OrphanResource;
+  deleted: boolean;
+  error?: string;
+}
+
+export type ActivityKind =
+  | { type: 'plan_generated'; plan: Plan }
+  | { type: 'plan_approved'; planId: string }
+  | { type: 'progress_updated'; title: string; description: string }
+  | { type: 'agent_messaged'; message: string }
+  | { type: 'user_messaged'; message: string }
+  | { type: 'session_completed' }
+  | { type: 'session_failed'; reason: string }
+  | { type: 'other' };
+
+export type Artifact =
+  | { type: 'bash_output'; command: string; output: string; exitCode: number }
+  | { type: 'change_set'; files: string[] }
+  | { type: 'media' };
+
+export interface Activity {
+  id: string;
+  createTime: string;
+  originator: string;
+  kind: ActivityKind;
+  artifacts: Artifact[];
+}
+
+export type SessionState =
+  | 'QUEUED'
+  | 'PLANNING'
+  | 'AWAITING_PLAN_APPROVAL'
+  | 'AWAITING_USER_FEEDBACK'
+  | 'IN_PROGRESS'
+  | 'PAUSED'
+  | 'FAILED'
+  | 'COMPLETED'
+  | 'STATE_UNSPECIFIED';
+
+export interface SessionSummary {
+  name: string; // "sessions/{id}"
+  title: string;
+  state: SessionState;
+  repo?: string; // "owner/repo"
+  url?: string;
+  pullRequest?: Omit<PrDetails, 'filesChanged'>;
+}
+
+export interface QuotaLimits {
+  maxConcurrent: number;
+  maxDaily: number; // Rolling 24 hours
+}
+
+export interface QuotaUsage {
+  limits: QuotaLimits;
+  active: number;
+  startedToday: number;
+  queued: number;
+  nextAvailable?: string; // RFC 3339, set when the daily limit is reached
+}
+
+export interface PollPolicy {
+  intervalSecs: number;
+  maxIntervalSecs: number; // Backoff ceiling while nothing changes
+  backoff: number;
+  fastIntervalSecs: number; // Right after approving or messaging
+  fastPolls: number;
+  maxRuntimeSecs: number;
+  maxConsecutiveErrors: number;
+  jitter: number; // +/- fraction of each interval
+}
+
+export interface JulesSource {
+  name: string; // "sources/github/{owner}/{repo}"
+  owner: string;
+  repo: string;
+}
+
+export type SourceResolution =
+  | { status: 'connected'; source: string }
+  | { status: 'not_installed'; repo: string; installUrl: string };
+
+export interface PlanStep {
+  index: number;
+  title: string;
+  description: string;
+}
+
+export interface Plan {
+  id: string;
+  steps: PlanStep[];
+  createTime: string;
+}
+
+export interface PlanRevision {
+  revision: number;
+  plan: Plan;
+  steps: { step: PlanStep; change: 'unchanged' | 'added' | 'modified' | 'removed' }[];
+}
+
+// Backend job records, as returned by list_jobs / get_job
+
+export type AutomationMode = 'AUTO_CREATE_PR' | 'AUTOMATION_MODE_UNSPECIFIED';
+
+// Unset fields keep the job's defaults: its own title, the repo's default branch, a PR at the end
+export interface SessionSettings {
+  title?: string;
+  startingBranch?: string;
+  automationMode?: AutomationMode;
+}
+
+export type JobParams =
+  | { kind: 'scaffold'; name: string; recipeId: string; context: string; mode: AgentMode; prompt?: string; session?: SessionSettings }
+  | { kind: 'uplink'; repoUrl: string; context: string; mode: AgentMode; prompt?: string; session?: SessionSettings }
+  | { kind: 'import'; sessionId: string };
+
+export interface StatusChange {
+  status: JobStatus;
+  at: string; // RFC 3339
+}
+
+export interface JobMessage {
+  text: string;
+  sentAt: string; // RFC 3339
+  acknowledged: boolean;
+}
+
+export type MergeMethod = 'merge' | 'squash' | 'rebase';
+
+// When an Auto mode job's PR may be merged without a human
+export interface MergePolicy {
+  method?: MergeMethod;        // Default squash
+  requireChecks?: boolean;     // Default true
+  checkTimeoutMins?: number;   // Default 60
+  maxChangedLines?: number;    // Additions plus deletions
+  allowedPaths?: string[];     // "dir/", "*.md" or exact paths; empty allows any
+}
+
+export interface JobRecord {
+  id: string;
+  githubRepo: string; // "owner/repo"
+  julesSessionId?: string;
+  status: JobStatus;
+  lastPoll?: number; // Unix seconds
+  approvalPending: boolean;
+  messages: JobMessage[];
+  prDetails?: Omit<PrDetails, 'filesChanged'>;
+  plans: Plan[];
+  warnings: string[];
+  failure?: JobFailure;
+  params?: JobParams;
+  history: StatusChange[];
+  createdAt: number; // Unix seconds
+  batchId?: string;
+  scheduleId?: string;
+  mergePolicy?: MergePolicy;
+}
+
+export interface JobQuery {
+  statuses?: JobStatusKey[];
+  repo?: string;         // Substring of "owner/repo"
+  kind?: JobParams['kind'];
+  batchId?: string;
+  scheduleId?: string;
+  createdAfter?: number; // Unix seconds, inclusive
+  createdBefore?: number; // Unix seconds, exclusive
+  offset?: number;
+  limit?: number;        // At most 200
+}
+
+export interface JobPage {
+  jobs: JobRecord[];
+  total: number;
+}
+
+export type BatchTarget =
+  | { kind: 'repos'; repos: string[] } // URLs or "owner/repo"
+  | { kind: 'org'; org: string; filter?: string; includeForks?: boolean; includeArchived?: boolean };
+
+export interface Batch {
+  id: string;
+  target: BatchTarget;
+  context: string; // "{repo}" is replaced per child
+  prompt: string;
+  mode: AgentMode;
+  jobIds: string[];
+  skipped: { repo: string; reason: string }[];
+  createdAt: number; // Unix seconds
+}
+
+// Payload of BATCH_UPDATE
+export interface BatchProgress {
+  batchId: string;
+  total: number;
+  prReady: number; // Including merged
+  failed: number;
+  cancelled: number;
+  completed: number; // Finished without a PR
+  queued: number;
+  interrupted: number;
+  running: number;
+  done: boolean;
+}
+
+// Prompts may use {repo}, {recipe} and {default_branch}, expanded when the session starts
+export interface PromptTemplate {
+  id: string; // Empty when saving a new template
+  name: string;
+  body: string;
+  builtin: boolean;
+}
+
+export type ScheduledTask =
+  | { kind: 'uplink'; repoUrl: string; context: string; mode: AgentMode; prompt?: string }
+  | { kind: 'batch'; target: BatchTarget; context: string; mode: AgentMode; prompt: string };
+
+// 'catch_up' starts one run for any number of missed ones
+export type MissedRuns = 'skip' | 'catch_up';
+
+export interface ScheduledRun {
+  scheduledFor: string; // RFC 3339
+  firedAt?: string;     // Unset when a missed run was skipped
+  jobId?: string;
+  batchId?: string;
+  error?: string;
+}
+
+// Payload of SCHEDULE_UPDATE
+export interface Schedule {
+  id: string;
+  name: string;
+  cron: string; // Five fields, local time, e.g. "0 9 * * MON"
+  task: ScheduledTask;
+  missedRuns: MissedRuns;
+  paused: boolean;
+  nextRun?: string; // RFC 3339, unset while paused
+  runs: ScheduledRun[];
+}
+
+export interface PlannedAction {
+  step: string;        // e.g. "create_codespace"
+  description: string;
+  details: string[];   // Diff lines start with "+ ", "- " or two spaces
+}
+
+// What a job would do, from a dry run of start_scaffold_job / start_uplink_job
+export interface DryRunReport {
+  repo: string; // "owner/repo"
+  actions: PlannedAction[];
+  error?: string; // Why a real run would stop at the last action
+}